@@ -0,0 +1,104 @@
+//! Compares per field (`update_field`) and per direction flood kernels on random mazes, and then
+//! dense flood with frontier only and cache blocked (tiled) ones on bigger mazes.
+//!
+//! Run with `cargo bench`; there is no benchmarking harness, every kernel is just timed on the
+//! same mazes, best of several runs is reported.
+
+use maze::{Flood, Maze, Solver, SparseFlood, TiledFlood};
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 3;
+
+/// Random maze with given size and percentage of empty fields
+fn generate(w: usize, h: usize, density: u32, mut seed: u64) -> Maze {
+    let mut input = String::new();
+    for y in 0..h {
+        for x in 0..w {
+            // xorshift - good enough to scatter walls
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+
+            let border = x == 0 || y == 0 || x == w - 1 || y == h - 1;
+            // Start and exit with fields next to them, so mazes are not trivially unreachable
+            let open = [(0, 1), (1, 1), (w - 2, h - 2), (w - 1, h - 2)].contains(&(x, y));
+            let empty = open || (!border && (seed % 100) < density as u64);
+            input.push(if empty { '1' } else { '0' });
+        }
+        input.push('\n');
+    }
+
+    let mut maze = Maze::from_input(w, h, Cursor::new(input));
+    maze.set_start(0, 1);
+    maze
+}
+
+/// Best time of full calculation, with the result to verify kernels agree
+fn time<T: Solver>(
+    maze: &Maze,
+    (w, h): (usize, usize),
+    create: impl Fn(Maze, usize, usize) -> T,
+) -> (Duration, String) {
+    (0..RUNS)
+        .map(|_| {
+            let mut flood = create(maze.clone(), w - 1, h - 2);
+            let started = Instant::now();
+            let status = flood.run();
+            (started.elapsed(), format!("{:?}", status))
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    println!(
+        "{:>11} {:>8} {:>14} {:>14} {:>8}",
+        "size", "density", "update_field", "per direction", "ratio"
+    );
+
+    for &(w, h) in &[(100, 100), (200, 200), (300, 300)] {
+        for &density in &[60, 75, 90] {
+            let maze = generate(w, h, density, 0x2545_f491_4f6c_dd1d);
+            let (field, field_status) = time(&maze, (w, h), Flood::new);
+            let (split, split_status) = time(&maze, (w, h), Flood::new_split);
+            assert_eq!(field_status, split_status, "kernels disagree");
+
+            println!(
+                "{:>11} {:>7}% {:>14?} {:>14?} {:>8.2}",
+                format!("{}x{}", w, h),
+                density,
+                field,
+                split,
+                split.as_secs_f64() / field.as_secs_f64()
+            );
+        }
+    }
+
+    println!();
+    println!(
+        "{:>11} {:>8} {:>14} {:>14} {:>14} {:>8}",
+        "size", "density", "dense", "frontier", "tiled", "ratio"
+    );
+
+    for &(w, h) in &[(200, 200), (400, 400)] {
+        for &density in &[60, 75, 90] {
+            let maze = generate(w, h, density, 0x2545_f491_4f6c_dd1d);
+            let (dense, dense_status) = time(&maze, (w, h), Flood::new);
+            let (frontier, frontier_status) = time(&maze, (w, h), SparseFlood::new);
+            let (tiled, tiled_status) = time(&maze, (w, h), TiledFlood::new);
+            assert_eq!(dense_status, frontier_status, "floods disagree");
+            assert_eq!(dense_status, tiled_status, "floods disagree");
+
+            println!(
+                "{:>11} {:>7}% {:>14?} {:>14?} {:>14?} {:>8.2}",
+                format!("{}x{}", w, h),
+                density,
+                dense,
+                frontier,
+                tiled,
+                tiled.as_secs_f64() / dense.as_secs_f64()
+            );
+        }
+    }
+}
@@ -0,0 +1,5 @@
+//! Maze solving part as a library, so solvers can be embedded in other applications (the binary
+//! is just one of their users).
+
+mod maze;
+pub use maze::*;
@@ -5,24 +5,106 @@
 //! just `thiserror`/`anyhow`. After all - invalid input is assumed to either crash, or give
 //! invalid output.
 //!
-//! Maze part is in `maze` module, conversion part is in `conv` module - those are basically two
-//! separated applications.
+//! Maze part is in `maze` module (exposed as library), conversion part is in `conv` module - those
+//! are basically two separated applications.
 //!
 //! I also don't create tests - I assume application to be just showup "POC", and as before about
 //! error handling - if I would find additional time, I would do something funny.
 
 use std::io::{stdin, BufRead, BufReader};
-use structopt::clap::arg_enum;
+use std::path::PathBuf;
+use std::time::Duration;
+use structopt::clap::{arg_enum, Error, ErrorKind};
 use structopt::StructOpt;
 
 mod bin;
-mod maze;
 
 arg_enum! {
     #[derive(Debug)]
     enum Alg {
         Flood,
         AStar,
+        FloodSparse,
+        FloodSplit,
+        FloodTiled,
+    }
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    enum Storage {
+        Dense,
+        Sparse,
+    }
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    enum Render {
+        Box,
+        Compact,
+    }
+}
+
+impl From<Render> for maze::Style {
+    fn from(render: Render) -> Self {
+        match render {
+            Render::Box => maze::Style::Box,
+            Render::Compact => maze::Style::Compact,
+        }
+    }
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    enum Critical {
+        Mask,
+        List,
+    }
+}
+
+impl From<Critical> for maze::CriticalOutput {
+    fn from(critical: Critical) -> Self {
+        match critical {
+            Critical::Mask => maze::CriticalOutput::Mask,
+            Critical::List => maze::CriticalOutput::List,
+        }
+    }
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    enum Format {
+        Text,
+        Json,
+    }
+}
+
+impl From<Format> for maze::Format {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Text => maze::Format::Text,
+            Format::Json => maze::Format::Json,
+        }
+    }
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    enum Export {
+        Csv,
+        Json,
+        Binary,
+    }
+}
+
+impl From<Export> for maze::Export {
+    fn from(export: Export) -> Self {
+        match export {
+            Export::Csv => maze::Export::Csv,
+            Export::Json => maze::Export::Json,
+            Export::Binary => maze::Export::Binary,
+        }
     }
 }
 
@@ -32,6 +114,120 @@ enum Mode {
     Maze {
         #[structopt(short, long, possible_values = &Alg::variants(), case_insensitive = true, default_value = "flood")]
         alg: Alg,
+        #[structopt(long, help = "Draws algorithm progress in terminal")]
+        visualize: bool,
+        #[structopt(
+            long,
+            default_value = "50",
+            help = "Delay between visualized frames in milliseconds"
+        )]
+        frame_delay: u64,
+        #[structopt(long, possible_values = &Render::variants(), case_insensitive = true, help = "Prints solved maze with path as plain text")]
+        render: Option<Render>,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Writes interactive HTML report of solving to given file"
+        )]
+        html: Option<PathBuf>,
+        #[structopt(long, help = "Stops calculation after given number of milliseconds")]
+        timeout: Option<u64>,
+        #[structopt(
+            long,
+            help = "Stops calculation after given number of flood iterations or A* expansions"
+        )]
+        max_steps: Option<usize>,
+        #[structopt(long, possible_values = &Format::variants(), case_insensitive = true, help = "Prints statistics of calculation to stderr")]
+        stats: Option<Format>,
+        #[structopt(long, possible_values = &Format::variants(), case_insensitive = true, default_value = "text", help = "Prints result as status line or as JSON object")]
+        format: Format,
+        #[structopt(
+            long,
+            help = "Checks if exit is reachable at all with fast bitboard flood before solving"
+        )]
+        precheck: bool,
+        #[structopt(
+            long,
+            help = "Prints number of all distinct optimal paths after the cost (UNKNOWN if the cost is not known to be optimal)"
+        )]
+        count_paths: bool,
+        #[structopt(
+            long,
+            help = "Outputs path picked uniformly from all optimal paths with given seed, instead of the reconstructed one"
+        )]
+        path_seed: Option<u64>,
+        #[structopt(long, possible_values = &Critical::variants(), case_insensitive = true, help = "Prints fields on any optimal path (and on all of them) after the cost (UNKNOWN if the cost is not known to be optimal)")]
+        critical: Option<Critical>,
+        #[structopt(
+            long,
+            help = "Prints given number of cheapest distinct routes after the cost"
+        )]
+        routes: Option<usize>,
+        #[structopt(long, help = "Routes never go through the same field twice")]
+        simple_routes: bool,
+        #[structopt(
+            long,
+            default_value = "0",
+            help = "Minimal number of fields of every route not on any other route"
+        )]
+        route_diversity: usize,
+        #[structopt(
+            long,
+            help = "Prints the best move towards the exit from every field after the cost"
+        )]
+        flow: bool,
+        #[structopt(
+            long,
+            help = "Prints every field reachable with at most given number of turns with its cost after the cost"
+        )]
+        isochrone: Option<usize>,
+        #[structopt(
+            long,
+            help = "Prints path as robot program (forward, left, right and turn around) after the cost"
+        )]
+        instructions: bool,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Writes cost and directions of every field of solved maze to given file (fields more expensive than the exit are not calculated, and with --timeout or --max-steps fields are written as they were when calculation stopped)"
+        )]
+        export: Option<PathBuf>,
+        #[structopt(long, possible_values = &Export::variants(), case_insensitive = true, default_value = "csv", help = "Format of exported fields")]
+        export_format: Export,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Solves out of core with flood, keeping maze in given file instead of memory (only the result is printed, so other algorithms, storages and outputs can't be used with it)"
+        )]
+        store: Option<PathBuf>,
+        #[structopt(long, possible_values = &Storage::variants(), case_insensitive = true, default_value = "dense", help = "Keeps maze fields in given storage (sparse stores runs of the same field, and can't be used with floodsplit)")]
+        storage: Storage,
+    },
+    #[structopt(about = "Runs all algorithms on the maze and verifies they agree")]
+    Compare {
+        #[structopt(
+            long,
+            default_value = "10000",
+            help = "Stops every algorithm after given number of milliseconds"
+        )]
+        timeout: u64,
+        #[structopt(
+            long,
+            help = "Stops every algorithm after given number of flood iterations or A* expansions"
+        )]
+        max_steps: Option<usize>,
+    },
+    #[structopt(about = "Checks if candidate path is valid and optimal")]
+    Verify {
+        #[structopt(
+            parse(from_os_str),
+            help = "File with candidate path, as x,y coordinates or as robot program"
+        )]
+        candidate: PathBuf,
+        #[structopt(long, help = "Stops solving after given number of milliseconds")]
+        timeout: Option<u64>,
+        #[structopt(long, help = "Stops solving after given number of flood iterations")]
+        max_steps: Option<usize>,
     },
     #[structopt(about = "Performs BIN -> DEC convetsion")]
     Conv,
@@ -44,6 +240,23 @@ struct Opt {
     mode: Option<Mode>,
 }
 
+/// Solves maze with given algorithm, keeping it in storage `S`
+fn solve<S: maze::Storage>(
+    alg: Alg,
+    x: usize,
+    y: usize,
+    input: impl BufRead,
+    options: maze::Options,
+) {
+    match alg {
+        Alg::Flood => maze::main::<S>(x, y, input, maze::flood, options),
+        Alg::AStar => maze::main::<S>(x, y, input, maze::astar, options),
+        Alg::FloodSparse => maze::main::<S>(x, y, input, maze::sparse_flood, options),
+        Alg::FloodSplit => maze::main::<S>(x, y, input, maze::split_flood, options),
+        Alg::FloodTiled => maze::main::<S>(x, y, input, maze::tiled_flood, options),
+    }
+}
+
 /// Takes buffered raed and just parses the first line as it is just metadata (and is probably
 /// irrelevant as lines are separated with `\n`, and assumption of reading until EOF should be good
 /// enaugh). Reason to read X and Y is to verify invariants.
@@ -64,9 +277,154 @@ fn main() {
     let mut input = BufReader::new(stdin());
     let (x, y) = read_xy(&mut input);
 
-    match opt.mode.unwrap_or(Mode::Maze { alg: Alg::Flood }) {
-        Mode::Maze { alg: Alg::Flood } => maze::main(x, y, input, maze::flood),
-        Mode::Maze { alg: Alg::AStar } => maze::main(x, y, input, maze::astar),
+    // Without subcommand maze is solved exactly as with `maze` subcommand with no arguments
+    let mode = opt
+        .mode
+        .unwrap_or_else(|| Mode::from_iter(&["fun_with_mazes", "maze"]));
+
+    match mode {
+        Mode::Maze {
+            alg,
+            visualize,
+            frame_delay,
+            render,
+            html,
+            timeout,
+            max_steps,
+            stats,
+            format,
+            precheck,
+            count_paths,
+            path_seed,
+            critical,
+            routes,
+            simple_routes,
+            route_diversity,
+            flow,
+            isochrone,
+            instructions,
+            export,
+            export_format,
+            store,
+            storage,
+        } => {
+            if visualize && matches!(format, Format::Json) {
+                Error::with_description(
+                    "--visualize draws to standard output, so it can't be used with --format json",
+                    ErrorKind::ArgumentConflict,
+                )
+                .exit();
+            }
+
+            if matches!((&alg, &storage), (Alg::FloodSplit, Storage::Sparse)) {
+                Error::with_description(
+                    "--alg floodsplit keeps per direction costs of every field, so it can't be used with --storage sparse",
+                    ErrorKind::ArgumentConflict,
+                )
+                .exit();
+            }
+
+            let limits = maze::Limits {
+                cancel: None,
+                timeout: timeout.map(Duration::from_millis),
+                max_steps,
+            };
+
+            if let Some(store) = store {
+                let unavailable = [
+                    ("--alg", !matches!(alg, Alg::Flood)),
+                    ("--storage", !matches!(storage, Storage::Dense)),
+                    ("--visualize", visualize),
+                    ("--render", render.is_some()),
+                    ("--html", html.is_some()),
+                    ("--stats", stats.is_some()),
+                    ("--precheck", precheck),
+                    ("--count-paths", count_paths),
+                    ("--path-seed", path_seed.is_some()),
+                    ("--critical", critical.is_some()),
+                    ("--routes", routes.is_some()),
+                    ("--simple-routes", simple_routes),
+                    ("--route-diversity", route_diversity != 0),
+                    ("--flow", flow),
+                    ("--isochrone", isochrone.is_some()),
+                    ("--instructions", instructions),
+                    ("--export", export.is_some()),
+                ];
+                if let Some((flag, _)) = unavailable.iter().find(|(_, used)| *used) {
+                    Error::with_description(
+                        &format!(
+                            "--store solves with flood kept in file and prints only the result, so it can't be used with {}",
+                            flag
+                        ),
+                        ErrorKind::ArgumentConflict,
+                    )
+                    .exit();
+                }
+
+                return maze::main_disk(x, y, input, &store, &limits, format.into());
+            }
+
+            let visualizer = if visualize {
+                Some(maze::Visualizer::new(Duration::from_millis(frame_delay)))
+            } else {
+                None
+            };
+
+            let options = maze::Options {
+                limits,
+                visualizer,
+                style: render.map(Into::into),
+                html,
+                stats: stats.map(Into::into),
+                format: format.into(),
+                algorithm: alg.to_string().to_lowercase(),
+                precheck,
+                count_paths,
+                path_seed,
+                critical: critical.map(Into::into),
+                routes: routes.map(|k| maze::RouteOptions {
+                    k,
+                    simple: simple_routes,
+                    diversity: route_diversity,
+                }),
+                flow,
+                isochrone,
+                instructions,
+                export: export.map(|file| (file, export_format.into())),
+            };
+
+            match storage {
+                Storage::Dense => solve::<maze::Dense>(alg, x, y, input, options),
+                Storage::Sparse => solve::<maze::Sparse>(alg, x, y, input, options),
+            }
+        }
+        Mode::Compare { timeout, max_steps } => {
+            let limits = maze::Limits {
+                cancel: None,
+                timeout: Some(Duration::from_millis(timeout)),
+                max_steps,
+            };
+
+            if !maze::compare(x, y, input, &limits) {
+                std::process::exit(1);
+            }
+        }
+        Mode::Verify {
+            candidate,
+            timeout,
+            max_steps,
+        } => {
+            let limits = maze::Limits {
+                cancel: None,
+                timeout: timeout.map(Duration::from_millis),
+                max_steps,
+            };
+
+            let candidate = std::fs::read_to_string(candidate).unwrap();
+            if !maze::verify(x, y, input, &candidate, &limits) {
+                std::process::exit(1);
+            }
+        }
         Mode::Conv => bin::main(y, input),
     }
 }
@@ -28,19 +28,93 @@
 //! In terms of visualization (even printing to text) - I don't even try to be efficient.
 
 use std::cmp::Ordering;
+use std::fmt::Write;
 use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+mod cell;
+use cell::Cell;
+
+mod storage;
+pub use storage::{Dense, Sparse, Storage};
+
+mod solver;
+pub use solver::{Bound, CancelToken, Limits, Solver, Status};
 
 mod flood;
-pub use flood::flood;
+pub use flood::{
+    disk_flood, flood, isochrone, reverse_flood, sparse_flood, split_flood, tiled_flood, DiskMaze,
+    ExitField, Flood, SparseFlood, TiledFlood,
+};
 
 mod astar;
-pub use astar::astar;
+pub use astar::{astar, AStar};
+
+mod observer;
+pub use observer::SolverObserver;
+
+mod visualize;
+pub use visualize::Visualizer;
+
+mod render;
+pub use render::{render, render_mask, Mark, Style};
+
+mod report;
+pub use report::Recorder;
+
+mod stats;
+pub use stats::{Format, Stats};
+
+mod compare;
+pub use compare::compare;
+
+mod export;
+pub use export::{export, Export, MAGIC, NO_COST, VERSION};
+
+mod instructions;
+pub use instructions::{execute, instructions, Instruction};
+
+mod verify;
+pub use verify::{parse_candidate, score, verify, Violation};
+
+mod reach;
+pub use reach::{reach, Reach};
+
+mod routes;
+pub use routes::{k_shortest, Route, RouteOptions};
+
+mod paths;
+pub use paths::{
+    count_optimal_paths, critical_cells, optimal_paths, sample_optimal_path, Critical, OptimalPaths,
+};
+
+/// Solving algorithm without any observer - takes initial maze and exit coordinates
+pub type Algorithm = fn(Maze, usize, usize, &Limits) -> (Maze, Status);
+
+/// Every implemented algorithm with its name
+pub const ALGORITHMS: &[(&str, Algorithm)] = &[
+    ("flood", |maze, x, y, limits| {
+        flood(maze, x, y, &mut (), limits)
+    }),
+    ("astar", |maze, x, y, limits| {
+        astar(maze, x, y, &mut (), limits)
+    }),
+    ("floodsparse", |maze, x, y, limits| {
+        sparse_flood(maze, x, y, &mut (), limits)
+    }),
+    ("floodsplit", |maze, x, y, limits| {
+        split_flood(maze, x, y, &mut (), limits)
+    }),
+    ("floodtiled", |maze, x, y, limits| {
+        tiled_flood(maze, x, y, &mut (), limits)
+    }),
+];
 
 /// Direction from which its needed to approach the field to achieve it with given cost. As it is
 /// possible to have same distance from multiple directions, it is a simple bitset. This is needed,
 /// as in oru problem cost of next step is dependent on the fact if there is a turn on this step.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-struct Dir(u8);
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Dir(u8);
 
 impl Dir {
     pub const NONE: Dir = Dir(0);
@@ -50,10 +124,19 @@ impl Dir {
     pub const DOWN: Dir = Dir(8);
     pub const ANY: Dir = Dir(1 | 2 | 4 | 8);
 
+    #[inline]
     pub fn has_all(&self, Dir(other): Dir) -> bool {
         self.0 & other == other
     }
 
+    /// Iterates over single directions encoded in this set
+    pub fn iter(self) -> impl Iterator<Item = Dir> {
+        [Self::LEFT, Self::UP, Self::RIGHT, Self::DOWN]
+            .iter()
+            .copied()
+            .filter(move |dir| self.has_all(*dir))
+    }
+
     /// Returns directions in which at least one step is needed
     pub fn vec((from_x, from_y): (usize, usize), (to_x, to_y): (usize, usize)) -> Self {
         let h = match from_x.cmp(&to_x) {
@@ -127,14 +210,15 @@ impl Dir {
 impl std::ops::BitOr for Dir {
     type Output = Self;
 
+    #[inline]
     fn bitor(self, rhs: Self) -> Self {
         Self(self.0 | rhs.0)
     }
 }
 
 /// Single field in maze
-#[derive(Clone, Copy, Debug)]
-enum Field {
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Field {
     Empty,
     Wall,
     /// Empty field with known distance from the start of the maze
@@ -142,15 +226,27 @@ enum Field {
     Calculated(Dir, usize),
 }
 
+impl Field {
+    /// Single character representation of field - distance is truncated to last digit
+    fn symbol(&self) -> char {
+        match self {
+            Field::Empty => ' ',
+            Field::Wall => '#',
+            Field::Calculated(_, distance) => (distance % 10).to_string().chars().last().unwrap(),
+        }
+    }
+}
+
 /// Whole maze reprezentation
-pub struct Maze {
+#[derive(Clone)]
+pub struct Maze<S = Dense> {
     /// All fields flattened
-    maze: Box<[Field]>,
+    maze: S,
     /// Width of maze as it is needed for proper addressing (inlcuding external wall)
     w: usize,
 }
 
-impl Maze {
+impl<S: Storage> Maze<S> {
     /// Maps coord to field index
     fn idx(&self, x: usize, y: usize) -> usize {
         // On overflow just give invalid (too big) index - anything from here would be wall by
@@ -165,41 +261,78 @@ impl Maze {
 
     /// Returns index of field in given direction (defined to be wrapping)
     fn in_dir_idx(&self, idx: usize, dir: Dir) -> usize {
-        let (x, y) = self.coords(idx);
-        // Doing wrapping sub basically because maze size is way smaller than my indexing type size
-        // (considering >= 16bit machine), so after wrapping I would have invalid field, so Wall by
-        // default
-        let (x, y) = match dir {
-            Dir::UP => (x, y.wrapping_sub(1)),
-            Dir::DOWN => (x, y + 1),
-            Dir::LEFT => (x.wrapping_sub(1), y),
-            Dir::RIGHT => (x + 1, y),
-            _ => (x, y),
-        };
+        // Exactly as moving coordinates and mapping them back with `idx`, but without division -
+        // it is called for every neighbour of every field in every flood iteration. Going up from
+        // the first row or left from the first column gives invalid index (so Wall by default),
+        // and going right from the last column wraps to the first field of the next row.
+        match dir {
+            Dir::UP => idx.checked_sub(self.w).unwrap_or(usize::MAX),
+            Dir::DOWN => idx.saturating_add(self.w),
+            Dir::LEFT if idx.is_multiple_of(self.w) => usize::MAX,
+            Dir::LEFT => idx - 1,
+            Dir::RIGHT => idx.saturating_add(1),
+            _ => idx,
+        }
+    }
 
-        self.idx(x, y)
+    /// Direction from which field is approached when going to it from `prev` - reverse of
+    /// `in_dir_idx`, including its wrapping; `None` if fields are not neighbours
+    fn approach(&self, prev: usize, idx: usize) -> Option<Dir> {
+        Dir::ANY
+            .iter()
+            .find(|dir| self.in_dir_idx(idx, *dir) == prev)
+    }
+
+    /// Index of field reached by going from given one with given heading (direction from which it
+    /// is approached); `None` if it is outside of the maze
+    fn forward(&self, idx: usize, heading: Dir) -> Option<usize> {
+        [
+            idx.wrapping_add(1),
+            idx.wrapping_sub(1),
+            idx.wrapping_add(self.w),
+            idx.wrapping_sub(self.w),
+        ]
+        .iter()
+        .copied()
+        .find(|next| *next < self.maze.len() && self.approach(idx, *next) == Some(heading))
+    }
+
+    /// Gives field with given index (Wall if no such field)
+    fn get(&self, idx: usize) -> Field {
+        self.maze.get_or_wall(idx)
+    }
+
+    /// Stores field on given index; Panics if its cost doesn't fit in packed field
+    fn set(&mut self, idx: usize, field: Field) {
+        self.maze.set(idx, field);
+    }
+
+    /// Iterates over all fields
+    fn fields(&self) -> impl Iterator<Item = Field> + '_ {
+        (0..self.maze.len()).map(move |idx| self.maze.get(idx))
+    }
+
+    /// Number of rows (the last one may be incomplete)
+    fn height(&self) -> usize {
+        self.maze.len().div_ceil(self.w)
     }
 
     /// Returns field in given direction from given one (Wall if no such field)
     /// If Dir has more than one direction encoded, field with same idx is returned
     fn in_dir(&self, idx: usize, dir: Dir) -> Field {
-        self.maze
-            .get(self.in_dir_idx(idx, dir))
-            .copied()
-            .unwrap_or(Field::Wall)
+        self.get(self.in_dir_idx(idx, dir))
     }
 
     /// Gives field from given coord (Wall if no such field)
     fn field(&self, x: usize, y: usize) -> Field {
-        self.maze
-            .get(self.idx(x, y))
-            .copied()
-            .unwrap_or(Field::Wall)
+        self.get(self.idx(x, y))
     }
 
-    /// Gives mutable field from given coord
-    fn field_mut(&mut self, x: usize, y: usize) -> Option<&mut Field> {
-        self.maze.get_mut(self.idx(x, y))
+    /// Marks given field as the start of the maze - calculated with no cost, and approachable from
+    /// any direction (so the first step is never a turn)
+    pub fn set_start(&mut self, x: usize, y: usize) {
+        let idx = self.idx(x, y);
+        self.set(idx, Field::Calculated(Dir::ANY, 0));
     }
 
     /// Creates valid maze from input containing maze description, and x/y dimentions of it
@@ -207,7 +340,7 @@ impl Maze {
         // Iterating over bytes is bad idea, but only interesting charactes are 0 and 1 which
         // happens to be ASCII bytes. I am aware it wont work with any non-ASCII UTF representation
         // of 0 and 1 and "I don't care, what they're going to say..."
-        let maze = input
+        let fields = input
             .lines()
             .take(y)
             .flat_map(|line| line.unwrap().into_bytes())
@@ -215,27 +348,65 @@ impl Maze {
                 b'0' => Field::Wall,
                 b'1' => Field::Empty,
                 _ => panic!("Invalid input"),
-            })
-            .collect();
+            });
+
+        Maze {
+            maze: S::from_fields(fields),
+            w: x,
+        }
+    }
+
+    /// Reconstructs one of the best paths from `start` to `exit` basing on calculated fields.
+    ///
+    /// Path is reconstructed backward - from every field one of the directions it is approached
+    /// from is taken, and previous field is verified to actually give this cost. If the previous
+    /// field is approached from the same direction with the same cost, the path have to keep going
+    /// straight there, as this is the only way to not turn. Returns `None` if exit is not
+    /// calculated, or calculated fields are not consistent enough to find the way back.
+    pub fn path(&self, start: (usize, usize), exit: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        let start = self.idx(start.0, start.1);
+        let mut idx = self.idx(exit.0, exit.1);
+        let mut heading = None;
+        let mut path = vec![self.coords(idx)];
+
+        while idx != start {
+            let (dirs, cost) = match self.get(idx) {
+                Field::Calculated(dirs, cost) => (dirs, cost),
+                _ => return None,
+            };
+
+            let (prev, prev_heading) = dirs
+                .iter()
+                .filter(|dir| heading.is_none_or(|heading| heading == *dir))
+                .find_map(|dir| {
+                    let prev = self.in_dir_idx(idx, dir);
+                    match self.get(prev) {
+                        Field::Calculated(pdirs, pcost) if pcost == cost && pdirs.has_all(dir) => {
+                            Some((prev, Some(dir)))
+                        }
+                        Field::Calculated(_, pcost) if pcost + 1 == cost => Some((prev, None)),
+                        _ => None,
+                    }
+                })?;
+
+            idx = prev;
+            heading = prev_heading;
+            path.push(self.coords(idx));
+        }
 
-        Maze { maze, w: x }
+        path.reverse();
+        Some(path)
     }
 }
 
-#[cfg(feature = "text_visualize")]
-impl std::fmt::Display for Maze {
+impl<S: Storage> std::fmt::Display for Maze<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         // While printing maze, externall wall is not printed
-        for line in self.maze.chunks(self.w) {
-            let line: String = line
-                .iter()
-                .map(|field| match field {
-                    Field::Empty => ' ',
-                    Field::Wall => '#',
-                    Field::Calculated(_, distance) => {
-                        (distance % 10).to_string().chars().last().unwrap()
-                    }
-                })
+        for y in 0..self.height() {
+            let line: String = (0..self.w)
+                .map(|x| self.idx(x, y))
+                .filter(|idx| *idx < self.maze.len())
+                .map(|idx| self.maze.get(idx).symbol())
                 .chain(std::iter::once('\n'))
                 .collect();
 
@@ -246,6 +417,61 @@ impl std::fmt::Display for Maze {
     }
 }
 
+/// Additional settings and outputs of maze solving
+pub struct Options {
+    /// Limits after which calculation is stopped
+    pub limits: Limits,
+    /// Draws progress in terminal
+    pub visualizer: Option<Visualizer>,
+    /// Prints solved maze with path before its cost
+    pub style: Option<Style>,
+    /// Writes report of the whole calculation to given HTML file
+    pub html: Option<PathBuf>,
+    /// Prints statistics of calculation to stderr in given format
+    pub stats: Option<Format>,
+    /// Format of the result - status line followed by other outputs, or single JSON object with
+    /// all details of the result, and other outputs (including statistics) as its optional fields;
+    /// Visualizer draws directly to terminal, so it shouldn't be used with JSON
+    pub format: Format,
+    /// Name of the algorithm, reported in JSON result
+    pub algorithm: String,
+    /// Checks if exit is reachable at all with bitboard flood before calculation, and gives up
+    /// immediately if it is not
+    pub precheck: bool,
+    /// Prints number of all distinct optimal paths after the cost
+    pub count_paths: bool,
+    /// Outputs path picked uniformly from all optimal paths with given seed, instead of the
+    /// reconstructed one
+    pub path_seed: Option<u64>,
+    /// Prints fields on optimal paths after the cost
+    pub critical: Option<CriticalOutput>,
+    /// Prints cheapest distinct routes after the cost
+    pub routes: Option<RouteOptions>,
+    /// Prints flow field towards the exit after the cost
+    pub flow: bool,
+    /// Prints all fields reachable from the start with at most given number of turns, with their
+    /// costs, after the cost
+    pub isochrone: Option<usize>,
+    /// Writes cost and directions of every field of solved maze to given file in given format -
+    /// if calculation is stopped by limits, fields are exported as they were when it stopped
+    pub export: Option<(PathBuf, Export)>,
+    /// Prints path as program of robot entering the maze (so facing right) after the cost
+    pub instructions: bool,
+}
+
+/// Output of fields on optimal paths
+#[derive(Clone, Copy, Debug)]
+pub enum CriticalOutput {
+    /// Maze with fields on any optimal path shaded, and chokepoints shaded darker (in rendering
+    /// style if given, box otherwise)
+    Mask,
+    /// Coordinates of every field on any optimal path as `x,y`, with `*` appended for chokepoints
+    List,
+}
+
+/// Observers used by `main`, every of them enabled by options
+type Observers = (Option<Visualizer>, (Option<Recorder>, Option<Stats>));
+
 /// As both "parts" of excercise are actually two separated applications, here we have maze "main"
 /// (with preparsed arguments).
 ///
@@ -256,26 +482,381 @@ impl std::fmt::Display for Maze {
 /// the closest path, and some another field calculated to have "at least this good" path.
 ///
 /// If there is no path to given exit, calculator should return maze with not calculated exit field
-pub fn main(
+///
+/// Calculator reports its progress to given observer, which is used for visualization and
+/// recording (if enabled in options), and stops when given limits are exceeded. Together with
+/// maze it returns final status of calculation.
+///
+/// Maze is kept in given storage, every output works with any of them.
+pub fn main<S: Storage>(
+    x: usize,
+    y: usize,
+    input: impl BufRead,
+    calculator: impl Fn(Maze<S>, usize, usize, &mut Observers, &Limits) -> (Maze<S>, Status),
+    options: Options,
+) {
+    let mut maze = Maze::<S>::from_input(x, y, input);
+    maze.set_start(0, 1);
+    let initial = options.isochrone.map(|_| maze.clone());
+
+    let exit = maze.field(x - 1, y - 2);
+    if options.precheck && exit != Field::Wall && !reach(&maze).reachable(x - 1, y - 2) {
+        let summary = Summary {
+            algorithm: &options.algorithm,
+            exit: (x - 1, y - 2),
+            path: &[],
+            stats: None,
+            fields: vec![],
+        };
+        return print_result(exit, Status::Exhausted, options.format, summary);
+    }
+
+    if let Some(visualizer) = &options.visualizer {
+        visualizer.clear();
+        visualizer.frame(&maze, std::iter::empty());
+    }
+
+    let recorder = options.html.as_ref().map(|_| Recorder::new(&maze));
+    let stats = options.stats.map(|_| Stats::new());
+    let mut observer = (options.visualizer, (recorder, stats));
+    let (maze, status) = calculator(maze, x - 1, y - 2, &mut observer, &options.limits);
+    let (visualizer, (recorder, stats)) = observer;
+
+    // Statistics are part of JSON result, and reported separately otherwise
+    let output = options.format;
+    let stats = stats
+        .zip(options.stats)
+        .and_then(|(stats, format)| match output {
+            Format::Text => {
+                eprintln!("{}", stats.report(format).trim_end());
+                None
+            }
+            Format::Json => Some(stats.report(Format::Json)),
+        });
+
+    let path = match options.path_seed {
+        Some(seed) => sample_optimal_path(&maze, (0, 1), (x - 1, y - 2), seed),
+        None => maze.path((0, 1), (x - 1, y - 2)),
+    }
+    .unwrap_or_default();
+
+    if let Some(visualizer) = &visualizer {
+        visualizer.path(&maze, &path);
+    }
+
+    let mut outputs = Outputs {
+        format: options.format,
+        fields: vec![],
+    };
+
+    if let Some(style) = options.style {
+        outputs.text("render", render(&maze, &path, style));
+    }
+
+    if let (Some(recorder), Some(html)) = (recorder, options.html) {
+        std::fs::write(html, recorder.html(&maze, &path)).unwrap();
+    }
+
+    if let Some((file, format)) = &options.export {
+        // Fields not cheaper than the exit depend on how far the algorithm got, so they are
+        // converged first - finished calculation exports the same fields with any algorithm
+        let limit = match status {
+            Status::Found(cost) => Some(Some(cost)),
+            Status::Exhausted => Some(None),
+            _ => None,
+        };
+        let converged = limit.map(|limit| {
+            let mut maze = maze.clone();
+            flood::converge(&mut maze, limit);
+            maze
+        });
+
+        let file = std::io::BufWriter::new(std::fs::File::create(file).unwrap());
+        export(converged.as_ref().unwrap_or(&maze), *format, file).unwrap();
+    }
+
+    if let Format::Text = options.format {
+        print_status(maze.field(x - 1, y - 2), status);
+    }
+
+    if options.instructions && !path.is_empty() {
+        let program: Vec<_> = instructions(&maze, &path, Dir::LEFT)
+            .iter()
+            .map(Instruction::to_string)
+            .collect();
+        let program = program.join(" ");
+        outputs.add(
+            "instructions",
+            format!("{}\n", program),
+            json_string(&program),
+        );
+    }
+
+    // Optimal paths are not known if the exit cost is not optimal - or if solving is stopped
+    // before the exit is reached, as it may be reachable anyway
+    let exit_unknown = matches!(status, Status::Incomplete(_))
+        && !matches!(maze.field(x - 1, y - 2), Field::Calculated(_, _));
+
+    if options.count_paths {
+        match count_optimal_paths(&maze, (0, 1), (x - 1, y - 2)).filter(|_| !exit_unknown) {
+            Some(count) => outputs.add("paths", format!("PATHS {}\n", count), count.to_string()),
+            None => outputs.add("paths", "PATHS UNKNOWN\n".to_owned(), "null".to_owned()),
+        }
+    }
+
+    if let Some(output) = options.critical {
+        let critical = critical_cells(&maze, (0, 1), (x - 1, y - 2)).filter(|_| !exit_unknown);
+        match (output, critical) {
+            (_, None) => outputs.add(
+                "critical",
+                "CRITICAL UNKNOWN\n".to_owned(),
+                "null".to_owned(),
+            ),
+            (CriticalOutput::Mask, Some(critical)) => {
+                let style = options.style.unwrap_or(Style::Box);
+                outputs.text("critical", render_mask(&maze, critical.mask(), style));
+            }
+            (CriticalOutput::List, Some(critical)) => {
+                let (mut text, mut json) = (String::new(), vec![]);
+                for (x, y) in critical.cells() {
+                    let chokepoint = critical.mask()[maze.idx(x, y)] == Mark::Chokepoint;
+                    let mark = if chokepoint { "*" } else { "" };
+                    writeln!(text, "{},{}{}", x, y, mark).unwrap();
+                    json.push(format!(
+                        "{{\"field\":[{},{}],\"chokepoint\":{}}}",
+                        x, y, chokepoint
+                    ));
+                }
+                outputs.add("critical", text, format!("[{}]", json.join(",")));
+            }
+        }
+    }
+
+    if let Some(routes) = options.routes {
+        let exit = (x - 1, y - 2);
+        let (mut text, mut json) = (String::new(), vec![]);
+        for route in k_shortest(&maze, (0, 1), exit, routes, &options.limits) {
+            let path: Vec<_> = route
+                .path
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect();
+            writeln!(text, "ROUTE {} {}", route.cost, path.join(" ")).unwrap();
+
+            let mut route_json = format!(
+                "{{\"cost\":{},\"path\":{}",
+                route.cost,
+                coords_json(&route.path)
+            );
+            if let Some(style) = options.style {
+                let rendered = render(&maze, &route.path, style);
+                text.push_str(&rendered);
+                write!(route_json, ",\"render\":{}", json_string(&rendered)).unwrap();
+            }
+            json.push(route_json + "}");
+        }
+        outputs.add("routes", text, format!("[{}]", json.join(",")));
+    }
+
+    if options.flow {
+        outputs.text("flow", flow_arrows(&maze, x - 1, y - 2));
+    }
+
+    if let (Some(initial), Some(budget)) = (initial, options.isochrone) {
+        let (mut text, mut json) = (String::new(), vec![]);
+        for ((x, y), cost) in isochrone(initial, budget) {
+            writeln!(text, "{},{} {}", x, y, cost).unwrap();
+            json.push(format!("{{\"field\":[{},{}],\"cost\":{}}}", x, y, cost));
+        }
+        outputs.add("isochrone", text, format!("[{}]", json.join(",")));
+    }
+
+    if let Format::Json = options.format {
+        let summary = Summary {
+            algorithm: &options.algorithm,
+            exit: (x - 1, y - 2),
+            path: &path,
+            stats,
+            fields: outputs.fields,
+        };
+        println!("{}", status_json(maze.field(x - 1, y - 2), status, summary));
+    }
+}
+
+/// Additional outputs of `main` - printed directly as text, or collected as optional fields of
+/// JSON result, so JSON is always the only output
+struct Outputs {
+    format: Format,
+    /// Collected JSON fields, as key and value
+    fields: Vec<(&'static str, String)>,
+}
+
+impl Outputs {
+    /// Prints given text, or adds given JSON value under given key
+    fn add(&mut self, key: &'static str, text: String, json: String) {
+        match self.format {
+            Format::Text => print!("{}", text),
+            Format::Json => self.fields.push((key, json)),
+        }
+    }
+
+    /// Prints given text, or adds it as JSON string under given key
+    fn text(&mut self, key: &'static str, text: String) {
+        let json = json_string(&text);
+        self.add(key, text, json);
+    }
+}
+
+/// Flow field towards the exit as text - the best move from every field (the first of them if
+/// there is more), walls as `#`, exit and fields with no way to exit as spaces
+fn flow_arrows<S: Storage>(maze: &Maze<S>, x: usize, y: usize) -> String {
+    let flow = reverse_flood(maze, x, y).flow(maze);
+    let mut result = String::new();
+
+    for (idx, moves) in flow.into_iter().enumerate() {
+        let c = match (maze.get(idx), moves.iter().next()) {
+            (Field::Wall, _) => '#',
+            (_, Some(Dir::LEFT)) => '←',
+            (_, Some(Dir::UP)) => '↑',
+            (_, Some(Dir::RIGHT)) => '→',
+            (_, Some(_)) => '↓',
+            (_, None) => ' ',
+        };
+
+        result.push(c);
+        if (idx + 1) % maze.w == 0 {
+            result.push('\n');
+        }
+    }
+
+    result
+}
+
+/// Solves maze out of core - fields are streamed into given file, and flood is calculated by
+/// tiles loaded from it, so the maze never has to fit in memory. Only the result is printed (in
+/// given format, without path), as anything else would need the whole maze.
+pub fn main_disk(
     x: usize,
     y: usize,
     input: impl BufRead,
-    calculator: impl Fn(Maze, usize, usize) -> Maze,
+    store: &Path,
+    limits: &Limits,
+    format: Format,
 ) {
-    let mut maze = Maze::from_input(x, y, input);
-    *maze.field_mut(0, 1).unwrap() = Field::Calculated(Dir::ANY, 0);
+    let mut maze = DiskMaze::from_input(x, y, input, store);
+    maze.set_start(0, 1);
+
+    let status = disk_flood(&mut maze, x - 1, y - 2, limits);
+    let summary = Summary {
+        algorithm: "disk",
+        exit: (x - 1, y - 2),
+        path: &[],
+        stats: None,
+        fields: vec![],
+    };
+    print_result(maze.field(x - 1, y - 2), status, format, summary);
+}
+
+/// Details of calculation reported in JSON result, besides its status
+struct Summary<'a> {
+    algorithm: &'a str,
+    exit: (usize, usize),
+    /// Found path, reported only if not empty
+    path: &'a [(usize, usize)],
+    /// Statistics already formatted as JSON object
+    stats: Option<String>,
+    /// Additional outputs, as key and JSON value
+    fields: Vec<(&'static str, String)>,
+}
+
+/// Prints result of calculation as status line, or JSON object with given details
+fn print_result(exit: Field, status: Status, format: Format, summary: Summary) {
+    match format {
+        Format::Text => print_status(exit, status),
+        Format::Json => println!("{}", status_json(exit, status, summary)),
+    }
+}
+
+/// Prints result of calculation basing on the exit field and final status
+fn print_status(exit: Field, status: Status) {
+    match (exit, status) {
+        (Field::Wall, _) => println!("INVALID"),
+        (_, Status::Found(cost)) => println!("{}", cost),
+        (_, Status::Incomplete(Bound { lower, upper })) => match upper {
+            Some(upper) => println!("INCOMPLETE >={} <={}", lower, upper),
+            None => println!("INCOMPLETE >={}", lower),
+        },
+        _ => println!("UNREACHABLE"),
+    }
+}
+
+/// Result of calculation as single JSON object - `status` is one of `found`, `unreachable`,
+/// `invalid` or `incomplete`, `cost` is `null` unless found, and `error` describes why it is not
+fn status_json(exit: Field, status: Status, summary: Summary) -> String {
+    let (name, cost, bound, error) = match (exit, status) {
+        (Field::Wall, _) => ("invalid", None, None, Some("exit is a wall")),
+        (_, Status::Found(cost)) => ("found", Some(cost), None, None),
+        (_, Status::Incomplete(bound)) => {
+            ("incomplete", None, Some(bound), Some("stopped by limits"))
+        }
+        _ => ("unreachable", None, None, Some("no path to exit")),
+    };
+
+    let null = || "null".to_owned();
+    let mut json = format!(
+        "{{\"status\":\"{}\",\"cost\":{},\"start\":[0,1],\"exit\":[{},{}],\"algorithm\":\"{}\"",
+        name,
+        cost.map_or_else(null, |cost| cost.to_string()),
+        summary.exit.0,
+        summary.exit.1,
+        summary.algorithm,
+    );
+
+    if let Some(Bound { lower, upper }) = bound {
+        let upper = upper.map_or_else(null, |upper| upper.to_string());
+        write!(
+            json,
+            ",\"bound\":{{\"lower\":{},\"upper\":{}}}",
+            lower, upper
+        )
+        .unwrap();
+    }
 
-    #[cfg(feature = "text_visualize")]
-    println!("Initial maze:\n\n{}\n", maze);
+    if !summary.path.is_empty() {
+        write!(json, ",\"path\":{}", coords_json(summary.path)).unwrap();
+    }
 
-    let maze = calculator(maze, x - 1, y - 2);
+    if let Some(stats) = summary.stats {
+        write!(json, ",\"stats\":{}", stats).unwrap();
+    }
 
-    #[cfg(feature = "text_visualize")]
-    println!("Calculated maze:\n\n{}\n", maze);
+    for (key, value) in summary.fields {
+        write!(json, ",\"{}\":{}", key, value).unwrap();
+    }
 
-    match maze.field(x - 1, y - 2) {
-        Field::Empty => println!("UNREACHABLE"),
-        Field::Wall => println!("INVALID"),
-        Field::Calculated(_, cost) => println!("{}", cost),
+    let error = error.map_or_else(null, |error| format!("\"{}\"", error));
+    write!(json, ",\"error\":{}}}", error).unwrap();
+    json
+}
+
+/// Coordinates of fields as JSON array of `[x,y]` arrays
+fn coords_json(path: &[(usize, usize)]) -> String {
+    let coords: Vec<_> = path.iter().map(|(x, y)| format!("[{},{}]", x, y)).collect();
+    format!("[{}]", coords.join(","))
+}
+
+/// Text as JSON string literal
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
     }
+    json.push('"');
+    json
 }
@@ -1,4 +1,4 @@
-use super::{Dir, Field, Maze};
+use super::{Bound, Dense, Dir, Field, Limits, Maze, Solver, SolverObserver, Status, Storage};
 use std::collections::BinaryHeap;
 
 /// Item to be stored on priority queue (aka binary heap) to find the best candidate for closest
@@ -26,24 +26,35 @@ impl std::cmp::Ord for QueueItem {
     }
 }
 
-struct AStar {
-    maze: Maze,
+/// A* search algorithm as resumable solver
+pub struct AStar<O = (), S = Dense> {
+    maze: Maze<S>,
     queue: BinaryHeap<QueueItem>,
     exit: (usize, usize),
+    observer: O,
+    /// Final status, once calculation is finished
+    status: Option<Status>,
 }
 
-impl AStar {
-    fn new(maze: Maze, x: usize, y: usize) -> Self {
+impl<S: Storage> AStar<(), S> {
+    pub fn new(maze: Maze<S>, x: usize, y: usize) -> Self {
+        Self::with_observer(maze, x, y, ())
+    }
+}
+
+impl<O: SolverObserver, S: Storage> AStar<O, S> {
+    /// Creates solver reporting every queue operation and field update to given observer
+    pub fn with_observer(maze: Maze<S>, x: usize, y: usize, mut observer: O) -> Self {
         let queue: BinaryHeap<_> = maze
             .maze
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, field)| match field {
+            .calculated()
+            .into_iter()
+            .filter_map(|idx| match maze.get(idx) {
                 Field::Calculated(dir, cost) => {
                     let v = Dir::vec((x, y), maze.coords(idx));
                     let rotations = dir.min_rotation(v);
                     Some(QueueItem {
-                        cost: *cost + rotations,
+                        cost: cost + rotations,
                         idx,
                     })
                 }
@@ -51,17 +62,28 @@ impl AStar {
             })
             .collect();
 
+        for item in queue.iter() {
+            observer.node_pushed(item.idx, item.cost);
+        }
+
         Self {
             queue,
             maze,
             exit: (x, y),
+            observer,
+            status: None,
         }
     }
 
+    pub fn into_maze(self) -> Maze<S> {
+        self.maze
+    }
+
     fn enqueue(&mut self, idx: usize) {
-        if let Field::Calculated(dir, cost) = self.maze.maze[idx] {
+        if let Field::Calculated(dir, cost) = self.maze.get(idx) {
             let v = Dir::vec(self.maze.coords(idx), self.exit);
             let rotations = dir.min_rotation(v);
+            self.observer.node_pushed(idx, cost + rotations);
             self.queue.push(QueueItem {
                 cost: cost + rotations,
                 idx,
@@ -69,7 +91,40 @@ impl AStar {
         }
     }
 
-    fn run(mut self) -> Maze {
+    /// Overwrites field with better (or equally good from another direction) value, and enqueues
+    /// it to be expanded
+    fn update(&mut self, idx: usize, field: Field) {
+        if let Field::Calculated(_, cost) = field {
+            let old = match self.maze.get(idx) {
+                Field::Calculated(_, old) => Some(old),
+                _ => None,
+            };
+            self.observer.cell_updated(idx, old, cost);
+        }
+
+        self.maze.set(idx, field);
+        self.enqueue(idx);
+    }
+
+    /// Finishes calculation with status basing on exit field
+    fn finish(&mut self) -> Status {
+        let (x, y) = self.exit;
+        let status = match self.maze.field(x, y) {
+            Field::Calculated(_, cost) => Status::Found(cost),
+            _ => Status::Exhausted,
+        };
+        self.status = Some(status);
+        self.observer.finished(&self.maze);
+        status
+    }
+}
+
+impl<O: SolverObserver, S: Storage> Solver<S> for AStar<O, S> {
+    fn step(&mut self) -> Status {
+        if let Some(status) = self.status {
+            return status;
+        }
+
         let dirs = [
             (Dir::LEFT, Dir::RIGHT),
             (Dir::RIGHT, Dir::LEFT),
@@ -77,43 +132,60 @@ impl AStar {
             (Dir::DOWN, Dir::UP),
         ];
 
-        while let Some(QueueItem { idx, .. }) = self.queue.pop() {
-            let field = self.maze.maze[idx];
-
-            for (from, to) in dirs.iter() {
-                let cost = match field {
-                    Field::Calculated(dir, cost) => cost + (!dir.has_all(*from) as usize),
-                    _ => continue,
-                };
-
-                let next_idx = self.maze.in_dir_idx(idx, *to);
-                match self.maze.maze.get(next_idx).copied().unwrap_or(Field::Wall) {
-                    Field::Calculated(dir, pcost) if pcost == cost => {
-                        self.maze.maze[next_idx] = Field::Calculated(dir | *from, cost);
-                        self.enqueue(next_idx);
-                    }
-                    Field::Calculated(_, pcost) if cost < pcost => {
-                        self.maze.maze[next_idx] = Field::Calculated(*from, cost);
-                        self.enqueue(next_idx);
-                    }
-                    Field::Empty => {
-                        self.maze.maze[next_idx] = Field::Calculated(*from, cost);
-                        self.enqueue(next_idx);
-                    }
-                    _ => (),
-                }
-            }
+        let (idx, cost) = match self.queue.pop() {
+            Some(QueueItem { idx, cost }) => (idx, cost),
+            None => return self.finish(),
+        };
 
-            #[cfg(feature = "text_visualize")]
-            println!("Next iteration:\n\n{}", self.maze);
+        self.observer.node_popped(&self.maze, idx, cost);
+        let field = self.maze.get(idx);
 
-            let (x, y) = self.exit;
-            if let Field::Calculated(_, _) = self.maze.field(x, y) {
-                break;
+        for (from, to) in dirs.iter() {
+            let cost = match field {
+                Field::Calculated(dir, cost) => cost + (!dir.has_all(*from) as usize),
+                _ => continue,
+            };
+
+            let next_idx = self.maze.in_dir_idx(idx, *to);
+            match self.maze.get(next_idx) {
+                Field::Calculated(dir, pcost) if pcost == cost => {
+                    self.update(next_idx, Field::Calculated(dir | *from, cost));
+                }
+                Field::Calculated(_, pcost) if cost < pcost => {
+                    self.update(next_idx, Field::Calculated(*from, cost));
+                }
+                Field::Empty => {
+                    self.update(next_idx, Field::Calculated(*from, cost));
+                }
+                _ => (),
             }
         }
 
-        self.maze
+        let (x, y) = self.exit;
+        if let Field::Calculated(_, _) = self.maze.field(x, y) {
+            return self.finish();
+        }
+
+        Status::Running
+    }
+
+    fn maze(&self) -> &Maze<S> {
+        &self.maze
+    }
+
+    fn bound(&self) -> Bound {
+        let (x, y) = self.exit;
+        let upper = match self.maze.field(x, y) {
+            Field::Calculated(_, cost) => Some(cost),
+            _ => None,
+        };
+        // Heuristic never overestimates, so nothing in queue can give better path than its top
+        let lower = self.queue.peek().map_or(0, |item| item.cost);
+
+        Bound {
+            lower: upper.map_or(lower, |upper| upper.min(lower)),
+            upper,
+        }
     }
 }
 
@@ -123,6 +195,17 @@ impl AStar {
 /// considered to be an "initial cost" of entering into the maze with this input, and additionally
 /// a field where we algorithm is looking path to. Returned maze contains exit field calculated to
 /// the closest path, and some another field calculated to have "at least this good" path.
-pub fn astar(maze: Maze, x: usize, y: usize) -> Maze {
-    AStar::new(maze, x, y).run()
+///
+/// Every queue operation and field update is reported to given observer (`()` for no-op).
+/// Calculation is stopped early if any of limits is exceeded, which is reflected in returned status.
+pub fn astar<S: Storage>(
+    maze: Maze<S>,
+    x: usize,
+    y: usize,
+    observer: &mut impl SolverObserver,
+    limits: &Limits,
+) -> (Maze<S>, Status) {
+    let mut astar = AStar::with_observer(maze, x, y, observer);
+    let status = astar.run_limited(limits);
+    (astar.into_maze(), status)
 }
@@ -0,0 +1,65 @@
+//! Packed storage of single field
+//!
+//! `Field` as an enum with `Dir` and `usize` takes 16 bytes, which is way too much for mazes with
+//! hundreds of millions of fields. In the maze fields are stored packed into `u32` - lowest 4 bits
+//! are directions, and the remaining 28 bits are the cost. Empty field has no directions (calculated
+//! field always has at least one), and wall is a sentinel with all bits set, so the highest cost
+//! which can be stored is one below it. Storing higher cost panics instead of silently wrapping.
+
+use super::{Dir, Field};
+
+const DIR_BITS: u32 = 4;
+const DIR_MASK: u32 = (1 << DIR_BITS) - 1;
+const WALL: u32 = u32::MAX;
+
+/// Highest cost which can be stored in the maze
+pub const MAX_COST: usize = (WALL >> DIR_BITS) as usize - 1;
+
+/// Field packed into 4 bytes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cell(u32);
+
+impl Cell {
+    pub const EMPTY: Cell = Cell(0);
+    pub const WALL: Cell = Cell(WALL);
+
+    /// Little endian bytes of packed field, for storing outside of memory
+    pub fn to_bytes(self) -> [u8; 4] {
+        self.0.to_le_bytes()
+    }
+
+    pub fn from_bytes(bytes: [u8; 4]) -> Self {
+        Cell(u32::from_le_bytes(bytes))
+    }
+}
+
+impl From<Field> for Cell {
+    #[inline]
+    fn from(field: Field) -> Self {
+        match field {
+            Field::Empty => Self::EMPTY,
+            Field::Wall => Self::WALL,
+            Field::Calculated(dir, cost) => {
+                assert!(dir != Dir::NONE, "Calculated field without direction");
+                assert!(
+                    cost <= MAX_COST,
+                    "Cost {} overflows packed field (max {})",
+                    cost,
+                    MAX_COST
+                );
+                Cell((cost as u32) << DIR_BITS | dir.0 as u32)
+            }
+        }
+    }
+}
+
+impl From<Cell> for Field {
+    #[inline]
+    fn from(Cell(cell): Cell) -> Self {
+        match (cell, cell & DIR_MASK) {
+            (WALL, _) => Field::Wall,
+            (_, 0) => Field::Empty,
+            (cell, dir) => Field::Calculated(Dir(dir as u8), (cell >> DIR_BITS) as usize),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Running all algorithms on the same maze and cross-checking their results
+//!
+//! If algorithms disagree, the one giving worse result has to be wrong somewhere. The place where
+//! it went wrong is found by following the path given by the better one - first field on it,
+//! which is not calculated as good by the worse algorithm, is where worse algorithm diverged.
+
+use super::{Field, Limits, Maze, Status, ALGORITHMS};
+use std::io::BufRead;
+use std::time::{Duration, Instant};
+
+/// Number of fields shown around the place where algorithms diverged in every direction
+const REGION: usize = 5;
+
+struct Run {
+    name: &'static str,
+    maze: Maze,
+    status: Status,
+    time: Duration,
+}
+
+impl Run {
+    fn result(&self) -> String {
+        match self.status {
+            Status::Found(cost) => cost.to_string(),
+            Status::Exhausted => "UNREACHABLE".to_owned(),
+            Status::Incomplete(_) => "INCOMPLETE".to_owned(),
+            Status::Running => "RUNNING".to_owned(),
+        }
+    }
+
+    /// Cost as ordering key - no path is worse than any path
+    fn cost(&self) -> usize {
+        match self.status {
+            Status::Found(cost) => cost,
+            _ => usize::MAX,
+        }
+    }
+}
+
+/// Prints region of both mazes side by side around given field
+fn print_region(better: &Run, worse: &Run, (x, y): (usize, usize)) {
+    println!(
+        "{} and {} diverge at ({}, {}):",
+        better.name, worse.name, x, y
+    );
+    println!("{:<w$}   {}", better.name, worse.name, w = 2 * REGION + 1);
+
+    let h = better.maze.height();
+    let xs = x.saturating_sub(REGION)..(x + REGION + 1).min(better.maze.w);
+    for ry in y.saturating_sub(REGION)..(y + REGION + 1).min(h) {
+        let line = |maze: &Maze| -> String {
+            xs.clone()
+                .map(|rx| {
+                    if (rx, ry) == (x, y) {
+                        '*'
+                    } else {
+                        maze.field(rx, ry).symbol()
+                    }
+                })
+                .collect()
+        };
+        println!(
+            "{:<w$}   {}",
+            line(&better.maze),
+            line(&worse.maze),
+            w = 2 * REGION + 1
+        );
+    }
+}
+
+/// Finds first field on the best path of `better` run, which is calculated worse by `worse` run
+fn divergence(
+    better: &Run,
+    worse: &Run,
+    start: (usize, usize),
+    exit: (usize, usize),
+) -> (usize, usize) {
+    let path = better.maze.path(start, exit).unwrap_or_default();
+    path.into_iter()
+        .find(
+            |(x, y)| match (better.maze.field(*x, *y), worse.maze.field(*x, *y)) {
+                (Field::Calculated(_, b), Field::Calculated(_, w)) => w > b,
+                _ => true,
+            },
+        )
+        .unwrap_or(exit)
+}
+
+/// Runs every algorithm on maze from input, and prints their results and timings. Returns `false`
+/// if algorithms don't agree on the exit cost - in such case region where the worse of them
+/// diverged from the better one is printed.
+///
+/// Every algorithm is run with given limits, so the one which doesn't finish doesn't block the
+/// comparison - it is reported as incomplete, which is a disagreement.
+pub fn compare(x: usize, y: usize, input: impl BufRead, limits: &Limits) -> bool {
+    let mut maze = Maze::from_input(x, y, input);
+    maze.set_start(0, 1);
+    let (start, exit) = ((0, 1), (x - 1, y - 2));
+
+    if let Field::Wall = maze.field(exit.0, exit.1) {
+        println!("INVALID");
+        return true;
+    }
+
+    let runs: Vec<_> = ALGORITHMS
+        .iter()
+        .map(|(name, algorithm)| {
+            let begin = Instant::now();
+            let (maze, status) = algorithm(maze.clone(), exit.0, exit.1, limits);
+            Run {
+                name,
+                maze,
+                status,
+                time: begin.elapsed(),
+            }
+        })
+        .collect();
+
+    println!("{:<12} {:>12} {:>14}", "algorithm", "result", "time");
+    for run in &runs {
+        println!(
+            "{:<12} {:>12} {:>14}",
+            run.name,
+            run.result(),
+            format!("{:?}", run.time)
+        );
+    }
+
+    let better = runs.iter().min_by_key(|run| run.cost()).unwrap();
+    let mut agree = true;
+    for worse in runs.iter().filter(|run| run.status != better.status) {
+        agree = false;
+        println!();
+        print_region(better, worse, divergence(better, worse, start, exit));
+    }
+
+    if !agree {
+        println!("\nDISAGREEMENT");
+    }
+
+    agree
+}
@@ -0,0 +1,118 @@
+//! Export of solved maze as a distance field
+//!
+//! Every field is exported with three values: whether it is a wall, its cost (none if it is not
+//! calculated), and directions it is approached from with this cost, as bits of `Dir` (left 1, up
+//! 2, right 4, down 8; zero if not calculated). Fields are always in row-major order, the same as
+//! in maze input, so the last row may be incomplete if input was.
+//!
+//! Fields of finished calculation are the same for every algorithm - fields up to the exit cost
+//! are exact, and the rest is not calculated. If calculation is stopped by limits, fields are
+//! exported as they were at that point, so they depend on the algorithm.
+//!
+//! Binary format is a header of five little-endian `u32` values - `MAGIC`, `VERSION`, width,
+//! height and number of fields (which is less than width times height if the last row is
+//! incomplete) - followed by three little-endian `u32` arrays with value of every field: wall (0
+//! or 1), cost (`NO_COST` if not calculated) and directions.
+
+use super::{Field, Maze, Storage};
+use std::convert::TryFrom;
+use std::io::{self, Write};
+
+/// The first four bytes of binary export
+pub const MAGIC: [u8; 4] = *b"MZDF";
+
+/// Version of binary export layout
+pub const VERSION: u32 = 1;
+
+/// Cost of not calculated field in binary export
+pub const NO_COST: u32 = u32::MAX;
+
+/// Format of exported distance field
+#[derive(Clone, Copy, Debug)]
+pub enum Export {
+    /// Line `x,y,wall,cost,dirs` for every field after the header line, cost is empty if not
+    /// calculated
+    Csv,
+    /// Single object with `width`, `height`, and `wall`, `cost` and `dirs` arrays of all fields,
+    /// cost is `null` if not calculated
+    Json,
+    /// Header and raw arrays, as described in module docs
+    Binary,
+}
+
+/// Name of exported array, with its value of single field as JSON
+type Column = (&'static str, fn(Field) -> String);
+
+/// Exported values of single field
+fn values(field: Field) -> (bool, Option<u32>, u32) {
+    match field {
+        Field::Wall => (true, None, 0),
+        Field::Empty => (false, None, 0),
+        Field::Calculated(dirs, cost) => (false, Some(u32::try_from(cost).unwrap()), dirs.0 as u32),
+    }
+}
+
+/// Writes the whole solved maze in given format
+pub fn export<S: Storage>(maze: &Maze<S>, format: Export, mut out: impl Write) -> io::Result<()> {
+    match format {
+        Export::Csv => {
+            writeln!(out, "x,y,wall,cost,dirs")?;
+            for (idx, field) in maze.fields().enumerate() {
+                let (x, y) = maze.coords(idx);
+                let (wall, cost, dirs) = values(field);
+                let cost = cost.map(|cost| cost.to_string()).unwrap_or_default();
+                writeln!(out, "{},{},{},{},{}", x, y, wall as u8, cost, dirs)?;
+            }
+        }
+        Export::Json => {
+            write!(out, "{{\"width\":{},\"height\":{}", maze.w, maze.height())?;
+
+            let arrays: [Column; 3] = [
+                ("wall", |field| (values(field).0 as u8).to_string()),
+                ("cost", |field| {
+                    values(field)
+                        .1
+                        .map_or_else(|| "null".to_owned(), |cost| cost.to_string())
+                }),
+                ("dirs", |field| values(field).2.to_string()),
+            ];
+            for (name, array) in arrays {
+                write!(out, ",\"{}\":[", name)?;
+                for (idx, field) in maze.fields().enumerate() {
+                    if idx > 0 {
+                        write!(out, ",")?;
+                    }
+                    write!(out, "{}", array(field))?;
+                }
+                write!(out, "]")?;
+            }
+
+            writeln!(out, "}}")?;
+        }
+        Export::Binary => {
+            out.write_all(&MAGIC)?;
+            let header = [
+                VERSION,
+                u32::try_from(maze.w).unwrap(),
+                u32::try_from(maze.height()).unwrap(),
+                u32::try_from(maze.maze.len()).unwrap(),
+            ];
+            for value in header {
+                out.write_all(&value.to_le_bytes())?;
+            }
+
+            let arrays: [fn(Field) -> u32; 3] = [
+                |field| values(field).0 as u32,
+                |field| values(field).1.unwrap_or(NO_COST),
+                |field| values(field).2,
+            ];
+            for array in arrays {
+                for field in maze.fields() {
+                    out.write_all(&array(field).to_le_bytes())?;
+                }
+            }
+        }
+    }
+
+    out.flush()
+}
@@ -1,16 +1,31 @@
-use super::{Dir, Field, Maze};
+use super::{
+    Bound, Cell, Dense, Dir, Field, Limits, Maze, Solver, SolverObserver, Status, Storage,
+};
 use rayon::prelude::*;
-use std::convert::identity as ident;
+
+mod sparse;
+pub use sparse::{sparse_flood, SparseFlood};
+
+mod split;
+
+mod tiled;
+pub use tiled::{tiled_flood, TiledFlood};
+
+mod disk;
+pub use disk::{disk_flood, DiskMaze};
+
+mod reverse;
+pub use reverse::{reverse_flood, ExitField};
 
 /// Calculates new cost of single field with directions from which the best value is achievable.
 ///
 /// input - previous iteration output
 /// idx - index of calculated field
-fn update_field(input: &Maze, idx: usize) -> (Dir, usize) {
+fn update_field<S: Storage>(input: &Maze<S>, idx: usize) -> (Dir, usize) {
     let dirs = [Dir::UP, Dir::DOWN, Dir::LEFT, Dir::RIGHT];
     let mut best = (Dir::NONE, 0);
 
-    if matches!(input.maze[idx], Field::Wall) {
+    if matches!(input.get(idx), Field::Wall) {
         return best;
     }
 
@@ -35,12 +50,58 @@ fn update_field(input: &Maze, idx: usize) -> (Dir, usize) {
     best
 }
 
+/// Calculates field for the next iteration, with the cost it is updated to (`None` if it is not
+/// updated to better value)
+///
+/// input - previous iteration output
+/// idx - index of calculated field
+fn next_field<S: Storage>(input: &Maze<S>, idx: usize) -> (Field, Option<usize>) {
+    merge(update_field(input, idx), input.get(idx))
+}
+
+/// Merges best cost calculated from neighbours with previous value of the field, giving the field
+/// for the next iteration and the cost it is updated to (`None` if it is not updated to better
+/// value)
+fn merge(updated: (Dir, usize), previous: Field) -> (Field, Option<usize>) {
+    match (updated, previous) {
+        ((Dir::NONE, _), field) => {
+            // Field has no update from any direction
+            (field, None)
+        }
+        ((udir, ucost), Field::Calculated(pdir, pcost)) if ucost == pcost => {
+            // Field is considered as updated only, if additional directions with this cost are
+            // added
+            if !pdir.has_all(udir) {
+                (Field::Calculated(udir | pdir, ucost), Some(ucost))
+            } else {
+                (Field::Calculated(udir, ucost), None)
+            }
+        }
+        ((udir, ucost), Field::Calculated(_, pcost)) if ucost < pcost => {
+            // Better path is found to this field. Even if the path is from another direction,
+            // path going out of this field cannot be worse that they were to be - the cost from
+            // this point can increase at max 1 (additional turn), but after all, cost is
+            // decreased for at least 1
+            (Field::Calculated(udir, ucost), Some(ucost))
+        }
+        ((dir, cost), Field::Empty) => {
+            // Calculated cost for previously not calculated field - it is best for now
+            (Field::Calculated(dir, cost), Some(cost))
+        }
+        (_, field) => {
+            // Updated value is not better than previous in any way, so nothing should be updated
+            (field, None)
+        }
+    }
+}
+
 /// Single iteration of loop, basically frame update. Normally done by GPU rasterizer, here for
 /// some profit I would just use Rayon.
 ///
 /// input - previous iteration output (front buffer)
 /// output - next iteration output (back buffer)
-/// updates - additional buffer marking which fields changed to better value; If there was no
+///
+/// Returns all fields which changed to better value, with their new costs. If there was no
 /// changes at all, it means that nothing more can be done for finding better way (if there is no
 /// path to exit yet, there is none at all). Additionally if the value on the current cost to exit
 /// is lower or equal to any updated field, it is also impossible to find better solution, so
@@ -49,63 +110,166 @@ fn update_field(input: &Maze, idx: usize) -> (Dir, usize) {
 /// As it would be more "idiomatic" or "functional", to return the new maze as result, I have in
 /// mind this is optimized for SIMD, so I am really into doing all the calculations inplace on
 /// existing buffers (instead of allocating backbuffer every frame).
-fn iteration(input: &Maze, output: &mut [Field], updates: &mut [Option<usize>]) {
-    let output = output.par_iter_mut();
-    let updates = updates.par_iter_mut();
-
+fn iteration<S: Storage>(input: &Maze<S>, output: &mut S) -> Vec<(usize, usize)> {
     // After all I figured out, that the whole calculation could be splitted even more - instead of
     // "per field", it could be "per field per direction" and after all merged - adding to that
     // filtering walls outside of "kernel" it would remove internal loop and reduce need of
-    // branching in kernel (however branching would be moved to "merging" phase - I leave it as
-    // possible improvement, but I would not spend more time on that.
-    output
-        .zip(updates)
-        .enumerate()
-        .for_each(|(idx, (output, update))| {
-            let updated = update_field(input, idx);
-
-            let (updated, change) = match (updated, input.maze[idx]) {
-                ((Dir::NONE, _), field) => {
-                    // Field has no update from any direction
-                    (field, None)
-                }
-                ((udir, ucost), Field::Calculated(pdir, pcost)) if ucost == pcost => {
-                    // Field is considered as updated only, if additional directions with this cost
-                    // are added
-                    if !pdir.has_all(udir) {
-                        (Field::Calculated(udir | pdir, ucost), Some(ucost))
-                    } else {
-                        (Field::Calculated(udir, ucost), None)
+    // branching in kernel (however branching would be moved to "merging" phase). It is
+    // implemented in `split` module, and `benches/kernels.rs` compares both.
+    output.par_fill(|idx| next_field(input, idx))
+}
+
+/// The lowest cost to which any field was updated in last iteration. No field can be updated to
+/// lower cost in any further iteration.
+fn best_update(updates: &[(usize, usize)]) -> Option<usize> {
+    updates.iter().map(|(_, cost)| *cost).min()
+}
+
+/// Recalculates given fields, all of them basing on the state before; Returns fields changed by
+/// this, and the best update of it. Updates to cost higher than `limit` are not applied at all,
+/// applied ones are reported to given observer.
+fn recalculate<S: Storage>(
+    maze: &mut Maze<S>,
+    candidates: impl ParallelIterator<Item = usize>,
+    limit: Option<usize>,
+    observer: &mut impl SolverObserver,
+) -> (Vec<usize>, Option<usize>) {
+    let input = &*maze;
+    let results: Vec<_> = candidates
+        .filter_map(|idx| {
+            let (field, update) = next_field(input, idx);
+            let limited = update.zip(limit).is_some_and(|(cost, limit)| cost > limit);
+            Some((idx, field, update)).filter(|_| !limited && field != input.get(idx))
+        })
+        .collect();
+
+    let changed = results.iter().map(|(idx, _, _)| *idx).collect();
+    let best = results.iter().filter_map(|(_, _, update)| *update).min();
+    for (idx, field, update) in results {
+        if let Some(cost) = update {
+            let old = match maze.get(idx) {
+                Field::Calculated(_, old) => Some(old),
+                _ => None,
+            };
+            observer.cell_updated(idx, old, cost);
+        }
+        maze.set(idx, field);
+    }
+
+    (changed, best)
+}
+
+/// Recalculates only fields around given changed ones (exactly as `SparseFlood` does), returns
+/// fields changed by this iteration, and the best update of it. Updates to cost higher than
+/// `limit` are not applied at all, applied ones are reported to given observer.
+fn frontier_iteration<S: Storage>(
+    maze: &mut Maze<S>,
+    changed: &[usize],
+    limit: Option<usize>,
+    observer: &mut impl SolverObserver,
+) -> (Vec<usize>, Option<usize>) {
+    let mut candidates: Vec<_> = changed
+        .iter()
+        .flat_map(|idx| sparse::dependants(maze, *idx))
+        .collect();
+    candidates.par_sort_unstable();
+    candidates.dedup();
+
+    recalculate(maze, candidates.into_par_iter(), limit, observer)
+}
+
+/// Iterates maze until no field changes at all. Solvers stop as soon as exit cannot get any
+/// cheaper, so fields with cost equal to the exit (including the exit itself) may still miss
+/// some of the directions they are approached from - after this every field has all of them.
+///
+/// With `limit` only fields up to this cost are calculated - fields more expensive are cleared
+/// first, and updates above it are never applied. Every calculated cost is cost of some real path,
+/// and path never gets cheaper on the way, so fields up to the limit end up exactly the same no
+/// matter what state maze was in before (how far and in which order any solver got), and the
+/// rest is empty. Tiled and disk floods end in this state, with the limit being the exit cost.
+///
+/// The first iteration goes through every field, but without collecting them - calculated part of
+/// the maze may be huge, and list of it would take more memory than the maze itself. Afterwards
+/// only fields around last changes are recalculated, so it is cheap for already solved maze.
+pub(super) fn converge<S: Storage>(maze: &mut Maze<S>, limit: Option<usize>) {
+    let mut convergence = Convergence::new(limit);
+    while !convergence.step(maze, &mut ()) {}
+}
+
+/// The same as `converge`, but performed iteration by iteration, so solvers can do it in their
+/// steps and be stopped by limits in the middle of it
+pub(super) struct Convergence {
+    limit: Option<usize>,
+    /// Fields changed in last iteration (`None` before the first one)
+    changed: Option<Vec<usize>>,
+}
+
+impl Convergence {
+    pub(super) fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            changed: None,
+        }
+    }
+
+    /// Performs single iteration, reporting every update to given observer; Returns if maze is
+    /// converged
+    pub(super) fn step<S: Storage>(
+        &mut self,
+        maze: &mut Maze<S>,
+        observer: &mut impl SolverObserver,
+    ) -> bool {
+        let changed = match &self.changed {
+            None => {
+                if let Some(limit) = self.limit {
+                    for idx in 0..maze.maze.len() {
+                        if matches!(maze.get(idx), Field::Calculated(_, cost) if cost > limit) {
+                            maze.set(idx, Field::Empty);
+                        }
                     }
                 }
-                ((udir, ucost), Field::Calculated(_, pcost)) if ucost < pcost => {
-                    // Better path is found to this field. Even if the path is from another
-                    // direction, path going out of this field cannot be worse that they were to be
-                    // - the cost from this point can increase at max 1 (additional turn), but
-                    // after all, cost is decreased for at least 1
-                    (Field::Calculated(udir, ucost), Some(ucost))
-                }
-                ((dir, cost), Field::Empty) => {
-                    // Calculated cost for previously not calculated field - it is best for now
-                    (Field::Calculated(dir, cost), Some(cost))
-                }
-                (_, field) => {
-                    // Updated value is not better than previous in any way, so nothing should be
-                    // updated
-                    (field, None)
-                }
-            };
 
-            *output = updated;
-            *update = change;
+                let all = (0..maze.maze.len()).into_par_iter();
+                recalculate(maze, all, self.limit, observer).0
+            }
+            Some(changed) => frontier_iteration(maze, changed, self.limit, observer).0,
+        };
+
+        let converged = changed.is_empty();
+        self.changed = Some(changed);
+        converged
+    }
+}
+
+/// Finds all fields reachable with at most `budget` turns from calculated fields of given maze
+/// (normally the start), with their costs (ordered by index).
+///
+/// Flood is stopped as soon as all updates of the last iteration exceed the budget, exactly as it
+/// is stopped on the exit cost when solving - no field can get any cheaper afterwards.
+pub fn isochrone<S: Storage>(mut maze: Maze<S>, budget: usize) -> Vec<((usize, usize), usize)> {
+    let mut changed = maze.maze.calculated();
+    loop {
+        let (next, best) = frontier_iteration(&mut maze, &changed, None, &mut ());
+        changed = next;
+        if best.is_none_or(|best| best > budget) {
+            break;
+        }
+    }
+
+    maze.maze
+        .calculated()
+        .into_iter()
+        .filter_map(|idx| match maze.get(idx) {
+            Field::Calculated(_, cost) if cost <= budget => Some((maze.coords(idx), cost)),
+            _ => None,
         })
+        .collect()
 }
 
 /// Predicate calulating, if algorithm should stop. It happens in two cases:
 /// 1. There was no updates on last iteration
 /// 2. All changes in last iteration updated their fields to cost higher or equal that current exit cost
-fn is_done(exit: Field, updates: &[Option<usize>]) -> bool {
-    let best = updates.par_iter().copied().filter_map(ident).min();
+fn is_done(exit: Field, best: Option<usize>) -> bool {
     match (best, exit) {
         (None, _) => true,
         (Some(best), Field::Calculated(_, exit)) if best >= exit => true,
@@ -113,24 +277,180 @@ fn is_done(exit: Field, updates: &[Option<usize>]) -> bool {
     }
 }
 
+/// Final status of flood basing on exit field
+fn exit_status(exit: Field) -> Status {
+    match exit {
+        Field::Calculated(_, cost) => Status::Found(cost),
+        _ => Status::Exhausted,
+    }
+}
+
+/// Bounds of exit cost, basing on the best update of last iteration (`None` before first one)
+fn bound(exit: Field, best: Option<usize>) -> Bound {
+    let upper = match exit {
+        Field::Calculated(_, cost) => Some(cost),
+        _ => None,
+    };
+    let lower = best.unwrap_or(0);
+
+    Bound {
+        lower: upper.map_or(lower, |upper| upper.min(lower)),
+        upper,
+    }
+}
+
+/// Way of calculating single iteration
+enum Kernel {
+    /// Every field is calculated from all its neighbours at once (`update_field`)
+    Field,
+    /// Every field is calculated separately for every direction, and results are merged
+    /// afterwards; Contains buffers for per direction results
+    Direction([Box<[u32]>; 4]),
+}
+
+/// Flood search algorithm as resumable solver
+pub struct Flood<O = (), S = Dense> {
+    maze: Maze<S>,
+    kernel: Kernel,
+    backbuffer: S,
+    exit: (usize, usize),
+    observer: O,
+    /// Best update of last iteration (`None` before first iteration)
+    best: Option<usize>,
+    /// Final status, once calculation is finished
+    status: Option<Status>,
+}
+
+impl<S: Storage> Flood<(), S> {
+    pub fn new(maze: Maze<S>, x: usize, y: usize) -> Self {
+        Self::with_observer(maze, x, y, ())
+    }
+
+    /// Creates solver using per direction kernel
+    pub fn new_split(maze: Maze<S>, x: usize, y: usize) -> Self {
+        Self::split_with_observer(maze, x, y, ())
+    }
+}
+
+impl<O: SolverObserver, S: Storage> Flood<O, S> {
+    /// Creates solver reporting every iteration and field update to given observer
+    pub fn with_observer(maze: Maze<S>, x: usize, y: usize, observer: O) -> Self {
+        Self::with_kernel(maze, x, y, observer, Kernel::Field)
+    }
+
+    /// Creates solver using per direction kernel, reporting every iteration and field update to
+    /// given observer. Per direction costs are kept for every field whatever the storage is, so
+    /// it takes memory proportional to the maze area even with sparse storage.
+    pub fn split_with_observer(maze: Maze<S>, x: usize, y: usize, observer: O) -> Self {
+        let len = maze.maze.len();
+        let buffer = || vec![0; len].into_boxed_slice();
+        let kernel = Kernel::Direction([buffer(), buffer(), buffer(), buffer()]);
+        Self::with_kernel(maze, x, y, observer, kernel)
+    }
+
+    fn with_kernel(maze: Maze<S>, x: usize, y: usize, observer: O, kernel: Kernel) -> Self {
+        // Backbuffer is overwritten by every iteration before being read, so initial value is
+        // irrelevant
+        let backbuffer = maze.maze.clone();
+
+        Self {
+            maze,
+            kernel,
+            backbuffer,
+            exit: (x, y),
+            observer,
+            best: None,
+            status: None,
+        }
+    }
+
+    pub fn into_maze(self) -> Maze<S> {
+        self.maze
+    }
+}
+
+impl<O: SolverObserver, S: Storage> Solver<S> for Flood<O, S> {
+    fn step(&mut self) -> Status {
+        if let Some(status) = self.status {
+            return status;
+        }
+
+        self.observer.iteration_started(&self.maze);
+        let updates = match &mut self.kernel {
+            Kernel::Field => iteration(&self.maze, &mut self.backbuffer),
+            Kernel::Direction(directions) => {
+                split::iteration(&self.maze, &mut self.backbuffer, directions)
+            }
+        };
+        std::mem::swap(&mut self.maze.maze, &mut self.backbuffer);
+
+        // Iteration itself is parallel, so updates are reported afterwards; backbuffer contains
+        // previous state at this point
+        for (idx, cost) in updates.iter().copied() {
+            let old = match self.backbuffer.get(idx) {
+                Field::Calculated(_, old) => Some(old),
+                _ => None,
+            };
+            self.observer.cell_updated(idx, old, cost);
+        }
+
+        let (x, y) = self.exit;
+        self.best = best_update(&updates);
+        if !is_done(self.maze.field(x, y), self.best) {
+            return Status::Running;
+        }
+
+        let status = exit_status(self.maze.field(x, y));
+        self.status = Some(status);
+        self.observer.finished(&self.maze);
+        status
+    }
+
+    fn maze(&self) -> &Maze<S> {
+        &self.maze
+    }
+
+    fn bound(&self) -> Bound {
+        let (x, y) = self.exit;
+        bound(self.maze.field(x, y), self.best)
+    }
+}
+
 /// Implementation of flood search algorithm
 ///
 /// As an argument it takes initial maze, with at least one field with known distance - which is
 /// considered to be an "initial cost" of entering into the maze with this input, and additionally
 /// a field where we algorithm is looking path to. Returned maze contains exit field calculated to
 /// the closest path, and some another field calculated to have "at least this good" path.
-pub fn flood(mut maze: Maze, x: usize, y: usize) -> Maze {
-    let mut backbuffer = vec![Field::Wall; maze.maze.len()].into_boxed_slice();
-
-    // Updates is initialized to anything which is not fully `None` - this is to ensure, that the
-    // iteration would not end before it starts.
-    let mut updates = vec![Some(0); maze.maze.len()].into_boxed_slice();
-    while !is_done(maze.field(x, y), &updates) {
-        iteration(&maze, &mut backbuffer, &mut updates);
-        std::mem::swap(&mut maze.maze, &mut backbuffer);
-        #[cfg(feature = "text_visualize")]
-        println!("Next iteration:\n\n{}", maze);
-    }
+///
+/// Every iteration and field update is reported to given observer (`()` for no-op). Calculation
+/// is stopped early if any of limits is exceeded, which is reflected in returned status.
+pub fn flood<S: Storage>(
+    maze: Maze<S>,
+    x: usize,
+    y: usize,
+    observer: &mut impl SolverObserver,
+    limits: &Limits,
+) -> (Maze<S>, Status) {
+    let mut flood = Flood::with_observer(maze, x, y, observer);
+    let status = flood.run_limited(limits);
+    (flood.into_maze(), status)
+}
 
-    maze
+/// Implementation of flood search algorithm using per direction kernel - gives exactly the same
+/// results as `flood`. It always takes memory proportional to the maze area (see
+/// `Flood::split_with_observer`), so it is not meant for sparse storage.
+///
+/// Every iteration and field update is reported to given observer (`()` for no-op). Calculation
+/// is stopped early if any of limits is exceeded, which is reflected in returned status.
+pub fn split_flood<S: Storage>(
+    maze: Maze<S>,
+    x: usize,
+    y: usize,
+    observer: &mut impl SolverObserver,
+    limits: &Limits,
+) -> (Maze<S>, Status) {
+    let mut flood = Flood::split_with_observer(maze, x, y, observer);
+    let status = flood.run_limited(limits);
+    (flood.into_maze(), status)
 }
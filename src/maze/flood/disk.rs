@@ -0,0 +1,417 @@
+//! Out of core flood
+//!
+//! Mazes bigger than memory are never loaded at once. Input is streamed directly into a file as
+//! bits of walls (1 bit per field, row by row, no header), and packed fields (4 bytes each) are
+//! appended to it in blocks of single tile - only for tiles which are ever calculated. Walls and
+//! areas flood never reaches are 32 times smaller than packed, and the file grows only as flood
+//! spreads. The price is a bit more than packed fields alone if flood reaches the whole maze (bits
+//! of walls, and blocks of edge tiles are never full), a lookup of tile block for every row of
+//! tile, and remapping of the file when it grows (its size is doubled every time, so it happens
+//! rarely). The file is memory mapped, so fields are read and written without any syscall, and the
+//! system keeps in memory only pages which are used recently. It is solved with the same tiles as
+//! tiled flood - every tile is copied out of the mapping together with its halo, iterated locally
+//! until it is stable, and copied back only if it changed. Tiles are processed in batches (so
+//! memory usage is limited by the batch size), and every batch is calculated in parallel.
+//!
+//! Active tiles are kept as single flag per tile for this round and the next one, and block of
+//! every tile as its number (so 10 bytes per 4096 fields), and they are taken in order of the
+//! file directly from flags - nothing proportional to the number of tiles is ever collected.
+//! Tiles are updated in place, so later batches see changes of earlier ones in the same round - it
+//! changes only the order in which fields converge, not where they converge to. Once exit is found,
+//! fields are converged up to its cost exactly as for tiled flood (in rounds stopped by limits as
+//! any other), so the final file doesn't depend on that order either.
+
+use super::tiled::{area, dependants, on_border, settle, tiles, TILE};
+use super::{bound, exit_status, is_done};
+use super::{Bound, Cell, Dir, Field, Limits, Maze, Status};
+use memmap2::MmapMut;
+use rayon::prelude::*;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// Number of tiles loaded at once for every thread
+const BATCH: usize = 4;
+
+/// Size of packed field in bytes
+const CELL: usize = 4;
+
+/// Size of block with packed fields of single tile in bytes
+const BLOCK: usize = TILE * TILE * CELL;
+
+/// Maze kept in a file instead of memory
+pub struct DiskMaze {
+    /// The file, kept to grow it when new blocks are needed
+    file: File,
+    /// Mapping of the whole file - bits of walls, and blocks after them
+    map: MmapMut,
+    /// Width of maze (inlcuding external wall)
+    w: usize,
+    /// Number of all fields
+    len: usize,
+    /// Offset of the first block in the file
+    blocks: usize,
+    /// Number of block of every tile (row by row), counted from 1 - 0 if tile is never calculated,
+    /// so its fields are given only by walls
+    table: Vec<usize>,
+    /// Number of blocks the file has space for
+    capacity: usize,
+    /// Number of blocks in use
+    used: usize,
+}
+
+impl DiskMaze {
+    /// Streams maze description into bits of walls in given file (which is created or
+    /// truncated), and maps it; Whole maze is never kept in memory
+    pub fn from_input(x: usize, y: usize, input: impl BufRead, path: &Path) -> Self {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .unwrap();
+
+        let mut writer = BufWriter::new(&file);
+        let (mut len, mut bits) = (0usize, 0u8);
+        for line in input.lines().take(y) {
+            for field in line.unwrap().bytes() {
+                let wall = match field {
+                    b'0' => 1,
+                    b'1' => 0,
+                    _ => panic!("Invalid input"),
+                };
+                bits |= wall << (len % 8);
+                len += 1;
+                if len.is_multiple_of(8) {
+                    writer.write_all(&[bits]).unwrap();
+                    bits = 0;
+                }
+            }
+        }
+        if !len.is_multiple_of(8) {
+            writer.write_all(&[bits]).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+
+        // File is created right here and used only through this mapping, so nothing else changes
+        // it under our feet
+        let map = unsafe { MmapMut::map_mut(&file) }.unwrap();
+        let (tx, ty) = tiles(x, len.div_ceil(x));
+        Self {
+            file,
+            map,
+            w: x,
+            len,
+            blocks: len.div_ceil(8),
+            table: vec![0; tx * ty],
+            capacity: 0,
+            used: 0,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.len.div_ceil(self.w)
+    }
+
+    /// Tells if field with given index (in the maze) is a wall
+    fn wall(&self, idx: usize) -> bool {
+        self.map[idx / 8] >> (idx % 8) & 1 == 1
+    }
+
+    /// Index of tile with given field in `table`
+    fn tile(&self, x: usize, y: usize) -> usize {
+        y / TILE * self.w.div_ceil(TILE) + x / TILE
+    }
+
+    /// Offset of given field in the file, in given block
+    fn offset(&self, block: usize, x: usize, y: usize) -> usize {
+        self.blocks + (block - 1) * BLOCK + (y % TILE * TILE + x % TILE) * CELL
+    }
+
+    /// Number of fields from given index to the end of its row in its tile (but not beyond the
+    /// maze), with coordinates of the field
+    fn segment(&self, idx: usize) -> (usize, usize, usize) {
+        let (x, y) = (idx % self.w, idx / self.w);
+        let count = (TILE - x % TILE).min(self.w - x).min(self.len - idx);
+        (x, y, count)
+    }
+
+    /// Block of given tile, which is added to the file (with walls of the tile) if tile doesn't
+    /// have it yet
+    fn block(&mut self, tile: usize) -> usize {
+        if self.table[tile] != 0 {
+            return self.table[tile];
+        }
+
+        if self.used == self.capacity {
+            self.capacity = (self.capacity * 2).max(1);
+            let size = self.blocks + self.capacity * BLOCK;
+            self.file.set_len(size as u64).unwrap();
+            // As for the initial mapping, the file is changed only through mapping
+            self.map = unsafe { MmapMut::map_mut(&self.file) }.unwrap();
+        }
+        self.used += 1;
+        self.table[tile] = self.used;
+
+        // New space of the file is zeroed, so all fields of the block are empty, only walls have
+        // to be written
+        let tiles = self.w.div_ceil(TILE);
+        let (x0, y0, w, h) = area(self.w, self.height(), (tile % tiles, tile / tiles));
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                let idx = y * self.w + x;
+                if idx >= self.len || self.wall(idx) {
+                    let offset = self.offset(self.used, x, y);
+                    self.map[offset..offset + CELL].copy_from_slice(&Cell::WALL.to_bytes());
+                }
+            }
+        }
+
+        self.used
+    }
+
+    /// Reads fields starting from given index; Fields out of maze are walls
+    fn read(&self, idx: usize, cells: &mut [Cell]) {
+        let mut done = 0;
+        while done < cells.len() {
+            let idx = idx.saturating_add(done);
+            if idx >= self.len {
+                cells[done..].fill(Cell::WALL);
+                return;
+            }
+
+            let (x, y, count) = self.segment(idx);
+            let count = count.min(cells.len() - done);
+            let part = &mut cells[done..done + count];
+            match self.table[self.tile(x, y)] {
+                0 => {
+                    for (offset, cell) in part.iter_mut().enumerate() {
+                        *cell = if self.wall(idx + offset) {
+                            Cell::WALL
+                        } else {
+                            Cell::EMPTY
+                        };
+                    }
+                }
+                block => {
+                    let offset = self.offset(block, x, y);
+                    let bytes = &self.map[offset..offset + count * CELL];
+                    for (cell, bytes) in part.iter_mut().zip(bytes.chunks(CELL)) {
+                        *cell = Cell::from_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    }
+                }
+            }
+            done += count;
+        }
+    }
+
+    /// Writes fields starting from given index; Fields out of maze are skipped (they are always
+    /// walls anyway)
+    fn write(&mut self, idx: usize, cells: &[Cell]) {
+        let mut done = 0;
+        while done < cells.len() {
+            let idx = idx.saturating_add(done);
+            if idx >= self.len {
+                return;
+            }
+
+            let (x, y, count) = self.segment(idx);
+            let count = count.min(cells.len() - done);
+            let part = &cells[done..done + count];
+            let tile = self.tile(x, y);
+
+            // Walls never change, so tile without block gets it only when anything is calculated
+            let calculated = part
+                .iter()
+                .any(|cell| *cell != Cell::WALL && *cell != Cell::EMPTY);
+            if self.table[tile] != 0 || calculated {
+                let block = self.block(tile);
+                let offset = self.offset(block, x, y);
+                let bytes = &mut self.map[offset..offset + count * CELL];
+                for (bytes, cell) in bytes.chunks_mut(CELL).zip(part) {
+                    bytes.copy_from_slice(&cell.to_bytes());
+                }
+            }
+            done += count;
+        }
+    }
+    /// Gives field from given coord (Wall if no such field)
+    pub(in crate::maze) fn field(&self, x: usize, y: usize) -> Field {
+        if x >= self.w {
+            return Field::Wall;
+        }
+
+        let mut cell = [Cell::WALL];
+        self.read(y.saturating_mul(self.w).saturating_add(x), &mut cell);
+        cell[0].into()
+    }
+
+    /// Marks given field as the start of the maze - calculated with no cost, and approachable from
+    /// any direction (so the first step is never a turn)
+    pub fn set_start(&mut self, x: usize, y: usize) {
+        self.write(y * self.w + x, &[Field::Calculated(Dir::ANY, 0).into()]);
+    }
+
+    /// Copies tile with its halo as separate maze
+    fn load(&self, tile: (usize, usize)) -> Maze {
+        let h = self.height();
+        let (x0, y0, w, th) = area(self.w, h, tile);
+        let mut local = vec![Cell::WALL; (w + 2) * (th + 2)];
+
+        for (ly, row) in local.chunks_mut(w + 2).enumerate() {
+            let y = (y0 + ly).wrapping_sub(1);
+            if y >= h {
+                continue;
+            }
+
+            // Row with halo is continuous in flattened maze, including wrapping of the right edge
+            // into the next row - only field left to the first column is always a wall
+            match x0 {
+                0 => self.read(y * self.w, &mut row[1..]),
+                _ => self.read(y * self.w + x0 - 1, row),
+            }
+        }
+
+        Maze {
+            maze: local.into_boxed_slice().into(),
+            w: w + 2,
+        }
+    }
+
+    /// Writes back tile loaded with `load` (without halo)
+    fn store(&mut self, tile: (usize, usize), local: &Maze) {
+        let (x0, y0, w, h) = area(self.w, self.height(), tile);
+
+        for y in 0..h {
+            let row = (y + 1) * (w + 2) + 1;
+            self.write((y0 + y) * self.w + x0, &local.maze[row..row + w]);
+        }
+    }
+
+    /// Clears every field more expensive than given cost - only tiles with blocks can have any
+    fn clear_above(&mut self, limit: usize) {
+        self.map[self.blocks..self.blocks + self.used * BLOCK]
+            .par_chunks_mut(CELL)
+            .for_each(|bytes| {
+                let cell = Cell::from_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                if matches!(Field::from(cell), Field::Calculated(_, cost) if cost > limit) {
+                    bytes.copy_from_slice(&Cell::EMPTY.to_bytes());
+                }
+            });
+    }
+}
+
+/// Calculates every active tile once, in batches loaded at once (every batch in parallel), without
+/// updates to cost higher than `limit`. Tiles affected by changes are activated for the next
+/// round. Returns the best update of the round.
+fn round(
+    maze: &mut DiskMaze,
+    tiles: (usize, usize),
+    active: &mut [bool],
+    limit: Option<usize>,
+) -> Option<usize> {
+    let size = rayon::current_num_threads() * BATCH;
+    let mut batch = Vec::with_capacity(size);
+    let mut best = None;
+    let mut next = vec![false; active.len()];
+
+    for idx in 0..active.len() {
+        if active[idx] {
+            batch.push((idx % tiles.0, idx / tiles.0));
+        }
+        if batch.is_empty() || (batch.len() < size && idx + 1 < active.len()) {
+            continue;
+        }
+
+        let mapped = &*maze;
+        let results: Vec<_> = batch
+            .par_iter()
+            .map(|tile| {
+                let mut local = mapped.load(*tile);
+                let initial = local.maze.clone();
+                let best = settle(&mut local, limit);
+                let changed: Vec<_> = (0..local.maze.len())
+                    .filter(|idx| local.maze[*idx] != initial[*idx])
+                    .collect();
+                let border = changed.iter().any(|idx| on_border(&local, *idx));
+                (local, best, !changed.is_empty(), border)
+            })
+            .collect();
+
+        for (tile, (local, update, changed, border)) in batch.drain(..).zip(results) {
+            if changed {
+                maze.store(tile, &local);
+            }
+
+            if border {
+                for (tx, ty) in dependants(tiles, tile) {
+                    next[ty * tiles.0 + tx] = true;
+                }
+            }
+
+            if let Some(update) = update {
+                best = Some(best.map_or(update, |best: usize| best.min(update)));
+            }
+        }
+    }
+
+    active.copy_from_slice(&next);
+    best
+}
+
+/// Implementation of flood search algorithm for maze kept in a file - finds the same exit cost as
+/// `flood`, fields in the file are updated in place.
+///
+/// Every step is a round over all tiles which could change - also once exit cost is known, and
+/// fields are converged up to it. Calculation is stopped early if any of limits is exceeded, which
+/// is reflected in returned status.
+pub fn disk_flood(maze: &mut DiskMaze, x: usize, y: usize, limits: &Limits) -> Status {
+    let start = Instant::now();
+    let tiles = tiles(maze.w, maze.height());
+    let mut active = vec![true; tiles.0 * tiles.1];
+    let mut best = None;
+    let mut steps = 0;
+
+    loop {
+        let exit = maze.field(x, y);
+        if limits.exceeded(start, steps) {
+            return Status::Incomplete(bound(exit, best));
+        }
+
+        // Nothing more expensive than already found exit can be on the best path
+        let limit = match exit {
+            Field::Calculated(_, cost) => Some(cost),
+            _ => None,
+        };
+        best = round(maze, tiles, &mut active, limit);
+        steps += 1;
+        if is_done(maze.field(x, y), best) {
+            break;
+        }
+    }
+
+    let status = exit_status(maze.field(x, y));
+    if let Status::Found(cost) = status {
+        // The same as `converge` - fields more expensive than the exit are cleared, and the rest
+        // is settled by tiles until nothing changes
+        maze.clear_above(cost);
+        active.iter_mut().for_each(|active| *active = true);
+        while active.iter().any(|active| *active) {
+            // Exit cost is already final, only fields more expensive than it are not
+            if limits.exceeded(start, steps) {
+                return Status::Incomplete(Bound {
+                    lower: cost,
+                    upper: Some(cost),
+                });
+            }
+
+            round(maze, tiles, &mut active, Some(cost));
+            steps += 1;
+        }
+    }
+
+    status
+}
@@ -0,0 +1,186 @@
+//! Reverse flood from the exit
+//!
+//! Regular flood calculates cost of reaching every field from the start. Here it is the other
+//! way - cost of reaching the exit from every field. Cost of going out of the field depends on
+//! the direction it was entered from, so it is calculated for every field and every direction
+//! separately (state) - exit has no cost from any direction, and every other state takes the
+//! cheapest of fields it can move to, plus one if the move is a turn.
+//!
+//! Only states of fields which can move to any field changed in the last iteration are
+//! recalculated (the same way as in `SparseFlood`), until nothing changes. Result is a distance
+//! field which many agents going to the same exit can just follow, without solving anything.
+
+use super::sparse::dependants;
+use super::{Dir, Field, Maze, Storage};
+use rayon::prelude::*;
+
+/// Marker of state from which exit is not reachable
+const NONE: u32 = u32::MAX;
+
+/// Costs of every direction of single field, indexed by `index`
+type Costs = [u32; 4];
+
+/// Index of single direction in `Costs` (in order of `Dir::iter`)
+fn index(dir: Dir) -> usize {
+    dir.0.trailing_zeros() as usize
+}
+
+/// Direction of the move which makes field approached from given direction
+fn opposite(dir: Dir) -> Dir {
+    dir.left().left()
+}
+
+/// Cost of reaching the exit from every field, created with `reverse_flood`
+pub struct ExitField {
+    w: usize,
+    exit: usize,
+    costs: Vec<Costs>,
+}
+
+impl ExitField {
+    /// Fields which can be reached directly from given one, with the direction they are
+    /// approached from (reverse of `Maze::in_dir_idx`, including its wrapping)
+    fn moves<'a, S: Storage>(
+        maze: &'a Maze<S>,
+        idx: usize,
+    ) -> impl Iterator<Item = (usize, Dir)> + 'a {
+        dependants(maze, idx)
+            .filter(move |next| *next != idx && maze.get(*next) != Field::Wall)
+            .flat_map(move |next| {
+                Dir::ANY
+                    .iter()
+                    .filter(move |dir| maze.in_dir_idx(next, *dir) == idx)
+                    .map(move |dir| (next, dir))
+            })
+    }
+
+    /// Recalculates costs of single field basing on fields it can move to
+    fn update<S: Storage>(maze: &Maze<S>, costs: &[Costs], idx: usize) -> Costs {
+        let mut result = [NONE; 4];
+        if maze.get(idx) == Field::Wall {
+            return result;
+        }
+
+        for (next, ndir) in Self::moves(maze, idx) {
+            let cost = costs[next][index(ndir)];
+            if cost == NONE {
+                continue;
+            }
+
+            for dir in Dir::ANY.iter() {
+                let cost = cost + (dir != ndir) as u32;
+                result[index(dir)] = result[index(dir)].min(cost);
+            }
+        }
+
+        result
+    }
+
+    /// Converts stored cost
+    fn cost_of(cost: u32) -> Option<usize> {
+        Some(cost as usize).filter(|_| cost != NONE)
+    }
+
+    /// Cost of reaching the exit from given field approached from given direction; With more
+    /// directions given (`Dir::ANY` for not moving yet), the cheapest of them is taken. `None`
+    /// if the exit is not reachable.
+    pub fn cost(&self, x: usize, y: usize, from: Dir) -> Option<usize> {
+        if x >= self.w {
+            return None;
+        }
+
+        let costs = self.costs.get(y * self.w + x)?;
+        Self::cost_of(from.iter().map(|dir| costs[index(dir)]).min()?)
+    }
+
+    /// The best move from given field approached from given direction (`Dir::ANY` for not
+    /// moving yet), as coordinates of the next field. If there is more best moves, going straight
+    /// is preferred. `None` on the exit, or if the exit is not reachable.
+    pub fn next<S: Storage>(
+        &self,
+        maze: &Maze<S>,
+        x: usize,
+        y: usize,
+        from: Dir,
+    ) -> Option<(usize, usize)> {
+        let idx = maze.idx(x, y);
+        let current = self.cost(x, y, from).filter(|_| idx != self.exit)?;
+
+        Self::moves(maze, idx)
+            .filter(|(next, ndir)| {
+                let cost = Self::cost_of(self.costs[*next][index(*ndir)]);
+                cost.map(|cost| cost + !from.has_all(*ndir) as usize) == Some(current)
+            })
+            .min_by_key(|(_, ndir)| !from.has_all(*ndir))
+            .map(|(next, _)| maze.coords(next))
+    }
+
+    /// Flow field - for every field (by index) all directions of the best moves, if not moving
+    /// yet (so turns of the first move are ignored). `Dir::NONE` for walls, the exit, and fields
+    /// from which exit is not reachable.
+    pub fn flow<S: Storage>(&self, maze: &Maze<S>) -> Vec<Dir> {
+        (0..self.costs.len())
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = maze.coords(idx);
+                let current = match self.cost(x, y, Dir::ANY) {
+                    Some(cost) if idx != self.exit => cost,
+                    _ => return Dir::NONE,
+                };
+
+                Self::moves(maze, idx)
+                    .filter(|(next, ndir)| {
+                        Self::cost_of(self.costs[*next][index(*ndir)]) == Some(current)
+                    })
+                    .fold(Dir::NONE, |flow, (_, ndir)| flow | opposite(ndir))
+            })
+            .collect()
+    }
+}
+
+/// Calculates cost of reaching the exit on given coordinates from every field of the maze
+/// (approached from every direction). Calculated fields of maze are ignored, only its walls are
+/// used.
+pub fn reverse_flood<S: Storage>(maze: &Maze<S>, x: usize, y: usize) -> ExitField {
+    let exit = maze.idx(x, y);
+    let mut costs = vec![[NONE; 4]; maze.maze.len()];
+    let mut changed = vec![];
+    if let Some(costs) = costs
+        .get_mut(exit)
+        .filter(|_| maze.get(exit) != Field::Wall)
+    {
+        *costs = [0; 4];
+        changed.push(exit);
+    }
+
+    while !changed.is_empty() {
+        // Fields which can move to changed ones
+        let mut candidates: Vec<_> = changed
+            .iter()
+            .flat_map(|idx| Dir::ANY.iter().map(move |dir| maze.in_dir_idx(*idx, dir)))
+            .filter(|idx| *idx < maze.maze.len() && *idx != exit)
+            .collect();
+        candidates.par_sort_unstable();
+        candidates.dedup();
+
+        let input = &costs;
+        let results: Vec<_> = candidates
+            .into_par_iter()
+            .filter_map(|idx| {
+                let updated = ExitField::update(maze, input, idx);
+                Some((idx, updated)).filter(|_| updated != input[idx])
+            })
+            .collect();
+
+        changed = results.iter().map(|(idx, _)| *idx).collect();
+        for (idx, updated) in results {
+            costs[idx] = updated;
+        }
+    }
+
+    ExitField {
+        w: maze.w,
+        exit,
+        costs,
+    }
+}
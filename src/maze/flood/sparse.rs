@@ -0,0 +1,149 @@
+//! Frontier only flood
+//!
+//! Dense flood recalculates every field in every iteration, but field can change only if any field
+//! it is calculated from changed in the previous iteration. Here only such fields are recalculated
+//! (still in parallel), so iteration is proportional to the frontier instead of whole maze. The
+//! field itself is also recalculated together with its neighbours, as merging new value depends
+//! on the previous one - this way results are exactly the same as for dense flood.
+
+use super::{bound, exit_status, is_done, next_field};
+use super::{Bound, Dense, Field, Limits, Maze, Solver, SolverObserver, Status, Storage};
+use rayon::prelude::*;
+
+/// Fields which calculation depends on the field with given index - it is reverse of
+/// `Maze::in_dir_idx`, including its wrapping of right edge to the next line
+pub(super) fn dependants<S: Storage>(maze: &Maze<S>, idx: usize) -> impl Iterator<Item = usize> {
+    let len = maze.maze.len();
+    let w = maze.w;
+
+    let left = idx.checked_sub(1);
+    let right = Some(idx + 1).filter(|right| right % w != 0);
+    let up = idx.checked_sub(w);
+    let down = Some(idx + w);
+
+    IntoIterator::into_iter([Some(idx), left, right, up, down])
+        .flatten()
+        .filter(move |idx| *idx < len)
+}
+
+/// Flood search algorithm recalculating only fields around last changes, as resumable solver
+pub struct SparseFlood<O = (), S = Dense> {
+    maze: Maze<S>,
+    /// Fields which changed in last iteration
+    changed: Vec<usize>,
+    exit: (usize, usize),
+    observer: O,
+    /// Best update of last iteration (`None` before first iteration)
+    best: Option<usize>,
+    /// Final status, once calculation is finished
+    status: Option<Status>,
+}
+
+impl<S: Storage> SparseFlood<(), S> {
+    pub fn new(maze: Maze<S>, x: usize, y: usize) -> Self {
+        Self::with_observer(maze, x, y, ())
+    }
+}
+
+impl<O: SolverObserver, S: Storage> SparseFlood<O, S> {
+    /// Creates solver reporting every iteration and field update to given observer
+    pub fn with_observer(maze: Maze<S>, x: usize, y: usize, observer: O) -> Self {
+        // Initially only fields around already calculated can change
+        let changed = maze.maze.calculated();
+
+        Self {
+            maze,
+            changed,
+            exit: (x, y),
+            observer,
+            best: None,
+            status: None,
+        }
+    }
+
+    pub fn into_maze(self) -> Maze<S> {
+        self.maze
+    }
+}
+
+impl<O: SolverObserver, S: Storage> Solver<S> for SparseFlood<O, S> {
+    fn step(&mut self) -> Status {
+        if let Some(status) = self.status {
+            return status;
+        }
+
+        self.observer.iteration_started(&self.maze);
+
+        let mut candidates: Vec<_> = self
+            .changed
+            .iter()
+            .flat_map(|idx| dependants(&self.maze, *idx))
+            .collect();
+        candidates.par_sort_unstable();
+        candidates.dedup();
+
+        let maze = &self.maze;
+        let results: Vec<_> = candidates
+            .into_par_iter()
+            .filter_map(|idx| {
+                let (field, update) = next_field(maze, idx);
+                Some((idx, field, update)).filter(|_| field != maze.get(idx))
+            })
+            .collect();
+
+        // All fields are calculated basing on previous state, so they are applied only after
+        // whole iteration
+        self.changed.clear();
+        self.best = None;
+        for (idx, field, update) in results {
+            let old = self.maze.get(idx);
+            self.maze.set(idx, field);
+            self.changed.push(idx);
+
+            if let Some(cost) = update {
+                let old = match old {
+                    Field::Calculated(_, old) => Some(old),
+                    _ => None,
+                };
+                self.observer.cell_updated(idx, old, cost);
+                self.best = Some(self.best.map_or(cost, |best| best.min(cost)));
+            }
+        }
+
+        let (x, y) = self.exit;
+        if !is_done(self.maze.field(x, y), self.best) {
+            return Status::Running;
+        }
+
+        let status = exit_status(self.maze.field(x, y));
+        self.status = Some(status);
+        self.observer.finished(&self.maze);
+        status
+    }
+
+    fn maze(&self) -> &Maze<S> {
+        &self.maze
+    }
+
+    fn bound(&self) -> Bound {
+        let (x, y) = self.exit;
+        bound(self.maze.field(x, y), self.best)
+    }
+}
+
+/// Implementation of frontier only flood search algorithm - gives exactly the same results as
+/// `flood`, but iteration cost is proportional to number of fields changed in previous one.
+///
+/// Every iteration and field update is reported to given observer (`()` for no-op). Calculation
+/// is stopped early if any of limits is exceeded, which is reflected in returned status.
+pub fn sparse_flood<S: Storage>(
+    maze: Maze<S>,
+    x: usize,
+    y: usize,
+    observer: &mut impl SolverObserver,
+    limits: &Limits,
+) -> (Maze<S>, Status) {
+    let mut flood = SparseFlood::with_observer(maze, x, y, observer);
+    let status = flood.run_limited(limits);
+    (flood.into_maze(), status)
+}
@@ -0,0 +1,75 @@
+//! Per direction flood kernel
+//!
+//! Instead of calculating every field from all its neighbours at once (with internal loop and
+//! branching on every direction), iteration is split into two phases. First, for every direction
+//! separately, cost of approaching every field from this direction is calculated into its own
+//! buffer - this is the "kernel", with no internal loop, and not caring about walls at all. Then
+//! all four buffers are merged, and walls are filtered out at this point. Merging picks the best
+//! cost and all directions it is achievable from, exactly as `update_field` does, so results are
+//! the same as for dense flood.
+//!
+//! On CPU (with Rayon) it turns out to be about twice slower than per field kernel (see
+//! `cargo bench`) - writing and reading back four additional buffers costs more than branching it
+//! avoids. Layout is meant for wide SIMD or GPU, where branching in kernel is expensive.
+
+use super::{merge, Dir, Field, Maze, Storage};
+use rayon::prelude::*;
+
+/// Directions in order of per direction buffers
+const DIRS: [Dir; 4] = [Dir::UP, Dir::DOWN, Dir::LEFT, Dir::RIGHT];
+
+/// Marker of no cost calculated from given direction
+const NONE: u32 = u32::MAX;
+
+/// Cost of approaching field from given direction (`NONE` if not approachable yet); Stored costs
+/// are limited by packed field, so even with additional turn they always fit in `u32`
+fn candidate<S: Storage>(input: &Maze<S>, idx: usize, dir: Dir) -> u32 {
+    match input.in_dir(idx, dir) {
+        Field::Calculated(pdir, cost) => (cost + (!pdir.has_all(dir) as usize)) as u32,
+        _ => NONE,
+    }
+}
+
+/// Merges per direction costs into best cost with directions it is achievable from
+fn best<S: Storage>(input: &Maze<S>, idx: usize, directions: &[Box<[u32]>; 4]) -> (Dir, usize) {
+    if matches!(input.get(idx), Field::Wall) {
+        return (Dir::NONE, 0);
+    }
+
+    let costs = [
+        directions[0][idx],
+        directions[1][idx],
+        directions[2][idx],
+        directions[3][idx],
+    ];
+    let best = costs.iter().copied().min().unwrap();
+    if best == NONE {
+        return (Dir::NONE, 0);
+    }
+
+    let dir = DIRS
+        .iter()
+        .zip(costs.iter())
+        .filter(|(_, cost)| **cost == best)
+        .fold(Dir::NONE, |acc, (dir, _)| acc | *dir);
+
+    (dir, best as usize)
+}
+
+/// Single iteration of flood using per direction kernel; Arguments and result are the same as
+/// for `flood::iteration`, with additional buffers for per direction costs.
+pub(super) fn iteration<S: Storage>(
+    input: &Maze<S>,
+    output: &mut S,
+    directions: &mut [Box<[u32]>; 4],
+) -> Vec<(usize, usize)> {
+    for (buffer, dir) in directions.iter_mut().zip(DIRS.iter()) {
+        buffer
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(idx, cost)| *cost = candidate(input, idx, *dir));
+    }
+
+    let directions = &*directions;
+    output.par_fill(|idx| merge(best(input, idx, directions), input.get(idx)))
+}
@@ -0,0 +1,335 @@
+//! Cache blocked flood
+//!
+//! Dense flood goes through the whole maze on every iteration, and any single field is visited
+//! once per iteration - so there is nothing gained from cache at all. Here maze is split into
+//! tiles small enough to fit in L1/L2 together with their halo (fields directly around tile), and
+//! every tile is iterated locally until it is stable. Then halos are exchanged, and only tiles
+//! which have any changed field in the halo are calculated in the next round - tiles with no
+//! changes on their border can't affect neighbours, so they are not even touched. Once exit is
+//! reached, updates more expensive than it are not applied, so tiles don't settle parts of the
+//! maze which can't be on the best path.
+//!
+//! Within a tile fields are updated in place and only around changes, so a whole path through the
+//! tile is calculated in single round, while dense flood needs an iteration over the whole maze
+//! for every field of it. It is over an order of magnitude faster than dense flood, and about
+//! twice faster than frontier only flood on mazes with path to exit (see `cargo bench`).
+//!
+//! Within a round tiles are independent (they read halos from the previous round) so they are
+//! calculated in parallel. The same kernel (`next_field`) is used, halos are taken exactly as
+//! `Maze::in_dir` does, including wrapping of the right edge, and it stops on the same condition
+//! (`is_done`), so it finds the same exit cost as dense flood. Tiles get further than single
+//! iterations, so fields not cheaper than the exit depend on order in which tiles were calculated
+//! when it stops - they are converged up to the exit cost in following steps (see `converge`), so
+//! the final maze doesn't depend on it.
+
+use super::{bound, exit_status, is_done, next_field, sparse, Convergence};
+use super::{Bound, Dense, Field, Limits, Maze, Solver, SolverObserver, Status, Storage};
+use rayon::prelude::*;
+use std::collections::VecDeque;
+
+/// Size of tile side - 64x64 packed fields takes 16KB
+pub(super) const TILE: usize = 64;
+
+/// Number of tiles horizontally and vertically in maze of given size
+pub(super) fn tiles(w: usize, h: usize) -> (usize, usize) {
+    (w.div_ceil(TILE), h.div_ceil(TILE))
+}
+
+/// Area covered by given tile in maze of given size, as (x, y, w, h)
+pub(super) fn area(w: usize, h: usize, (tx, ty): (usize, usize)) -> (usize, usize, usize, usize) {
+    let (x, y) = (tx * TILE, ty * TILE);
+    (x, y, TILE.min(w - x), TILE.min(h - y))
+}
+
+/// Iterates tile with its halo (so the outer ring of given maze is not calculated) until it is
+/// stable, returns the best update of it. Tile is small enough to stay in cache, so fields are
+/// updated in place, and only fields around changed ones are recalculated - every field is
+/// visited about as many times as it changes, instead of whole tile being iterated over and over.
+/// Updates to cost higher than `limit` are not applied at all.
+pub(super) fn settle(local: &mut Maze, limit: Option<usize>) -> Option<usize> {
+    let (w, h) = (local.w, local.maze.len() / local.w);
+    let mut queue: VecDeque<_> = (1..h - 1)
+        .flat_map(|y| (1..w - 1).map(move |x| y * w + x))
+        .collect();
+    let mut queued = vec![true; local.maze.len()];
+
+    let mut best: Option<usize> = None;
+    while let Some(idx) = queue.pop_front() {
+        queued[idx] = false;
+        let (field, update) = next_field(local, idx);
+        let limited = update.zip(limit).is_some_and(|(cost, limit)| cost > limit);
+        if limited || field == local.get(idx) {
+            continue;
+        }
+
+        local.set(idx, field);
+        if let Some(cost) = update {
+            best = Some(best.map_or(cost, |best| best.min(cost)));
+        }
+
+        for dependant in sparse::dependants(local, idx) {
+            let (x, y) = local.coords(dependant);
+            let interior = x > 0 && y > 0 && x < w - 1 && y < h - 1;
+            if interior && !queued[dependant] {
+                queued[dependant] = true;
+                queue.push_back(dependant);
+            }
+        }
+    }
+
+    best
+}
+
+/// If given field of tile with halo is on the tile border (so it is in halo of another tile)
+pub(super) fn on_border(local: &Maze, idx: usize) -> bool {
+    let (x, y) = local.coords(idx);
+    x == 1 || y == 1 || x == local.w - 2 || y == local.maze.len() / local.w - 2
+}
+
+/// Tiles which halo contains border of given tile
+pub(super) fn dependants(
+    (tw, th): (usize, usize),
+    (tx, ty): (usize, usize),
+) -> impl Iterator<Item = (usize, usize)> {
+    let mut dependants = vec![
+        (tx.wrapping_sub(1), ty),
+        (tx + 1, ty),
+        (tx, ty.wrapping_sub(1)),
+        (tx, ty + 1),
+    ];
+
+    // First column of the maze is in the halo of the last one, but a row above
+    if tx == 0 {
+        dependants.push((tw - 1, ty));
+        dependants.push((tw - 1, ty.wrapping_sub(1)));
+    }
+
+    dependants
+        .into_iter()
+        .filter(move |(x, y)| *x < tw && *y < th)
+}
+
+/// Single tile after local iteration
+struct Tile {
+    /// Tile coordinates
+    tile: (usize, usize),
+    /// All changed fields of tile as (index, old field, new field)
+    changed: Vec<(usize, Field, Field)>,
+    /// Best update of all local iterations
+    best: Option<usize>,
+    /// If any field on the tile border changed
+    border: bool,
+}
+
+/// Iterates single tile until it is stable, without updates to cost higher than `limit`; Tile
+/// itself is always kept dense, whatever the storage of the whole maze is
+fn tile<S: Storage>(maze: &Maze<S>, tile: (usize, usize), limit: Option<usize>) -> Tile {
+    let (x0, y0, w, h) = area(maze.w, maze.height(), tile);
+
+    // Local maze is the tile with its halo; Halo is taken with the same wrapping arithmetic as
+    // `Maze::in_dir_idx`, so anything out of maze is a wall, and the right edge wraps
+    let coords = |lx: usize, ly: usize| {
+        (
+            x0.wrapping_sub(1).wrapping_add(lx),
+            (y0 + ly).wrapping_sub(1),
+        )
+    };
+    let mut local = Maze {
+        maze: Dense::from_fields((0..(w + 2) * (h + 2)).map(|idx| {
+            let (x, y) = coords(idx % (w + 2), idx / (w + 2));
+            maze.field(x, y)
+        })),
+        w: w + 2,
+    };
+    let initial = local.clone();
+    let best = settle(&mut local, limit);
+
+    let mut border = false;
+    let changed = (0..local.maze.len())
+        .filter(|idx| local.maze[*idx] != initial.maze[*idx])
+        .map(|idx| {
+            border |= on_border(&local, idx);
+            let (lx, ly) = local.coords(idx);
+            let (x, y) = coords(lx, ly);
+            (maze.idx(x, y), initial.get(idx), local.get(idx))
+        })
+        .collect();
+
+    Tile {
+        tile,
+        changed,
+        best,
+        border,
+    }
+}
+
+/// Flood search algorithm calculating cache sized tiles separately, as resumable solver
+pub struct TiledFlood<O = (), S = Dense> {
+    maze: Maze<S>,
+    /// Number of tiles horizontally and vertically
+    tiles: (usize, usize),
+    /// Tiles to be calculated in the next round
+    active: Vec<bool>,
+    exit: (usize, usize),
+    observer: O,
+    /// Best update of last round (`None` before first round)
+    best: Option<usize>,
+    /// Convergence up to the exit cost, once it is found
+    converging: Option<Convergence>,
+    /// Final status, once calculation is finished
+    status: Option<Status>,
+}
+
+impl<S: Storage> TiledFlood<(), S> {
+    pub fn new(maze: Maze<S>, x: usize, y: usize) -> Self {
+        Self::with_observer(maze, x, y, ())
+    }
+}
+
+impl<O: SolverObserver, S: Storage> TiledFlood<O, S> {
+    /// Creates solver reporting every round and field update to given observer
+    pub fn with_observer(maze: Maze<S>, x: usize, y: usize, observer: O) -> Self {
+        let tiles = tiles(maze.w, maze.height());
+
+        Self {
+            maze,
+            tiles,
+            active: vec![true; tiles.0 * tiles.1],
+            exit: (x, y),
+            observer,
+            best: None,
+            converging: None,
+            status: None,
+        }
+    }
+
+    pub fn into_maze(self) -> Maze<S> {
+        self.maze
+    }
+}
+
+impl<O: SolverObserver, S: Storage> TiledFlood<O, S> {
+    /// Calculates all active tiles, and activates tiles affected by their changes
+    fn round(&mut self) {
+        let tw = self.tiles.0;
+        let active: Vec<_> = self
+            .active
+            .iter()
+            .enumerate()
+            .filter(|(_, active)| **active)
+            .map(|(idx, _)| (idx % tw, idx / tw))
+            .collect();
+        self.active.iter_mut().for_each(|active| *active = false);
+
+        // Nothing more expensive than already found exit can be on the best path, so such updates
+        // are not even applied - fields more expensive than the final exit are cleared anyway
+        let (x, y) = self.exit;
+        let limit = match self.maze.field(x, y) {
+            Field::Calculated(_, cost) => Some(cost),
+            _ => None,
+        };
+
+        let maze = &self.maze;
+        let tiles: Vec<_> = active
+            .into_par_iter()
+            .map(|idx| tile(maze, idx, limit))
+            .collect();
+
+        // All tiles are calculated basing on previous state, so they are applied only after
+        // whole round
+        self.best = None;
+        for tile in tiles {
+            for (idx, old, new) in tile.changed {
+                self.maze.set(idx, new);
+                if let Field::Calculated(_, cost) = new {
+                    let old = match old {
+                        Field::Calculated(_, old) => Some(old),
+                        _ => None,
+                    };
+                    self.observer.cell_updated(idx, old, cost);
+                }
+            }
+
+            if let Some(best) = tile.best {
+                self.best = Some(self.best.map_or(best, |b| b.min(best)));
+            }
+
+            if tile.border {
+                for (x, y) in dependants(self.tiles, tile.tile) {
+                    self.active[y * tw + x] = true;
+                }
+            }
+        }
+    }
+}
+
+impl<O: SolverObserver, S: Storage> Solver<S> for TiledFlood<O, S> {
+    fn step(&mut self) -> Status {
+        if let Some(status) = self.status {
+            return status;
+        }
+
+        self.observer.iteration_started(&self.maze);
+
+        let (x, y) = self.exit;
+        match &mut self.converging {
+            Some(convergence) => {
+                if !convergence.step(&mut self.maze, &mut self.observer) {
+                    return Status::Running;
+                }
+            }
+            None => {
+                self.round();
+                if !is_done(self.maze.field(x, y), self.best) {
+                    return Status::Running;
+                }
+
+                // Exit cost is final, fields are converged up to it in following steps, so it
+                // is stopped by limits as any other step
+                if let Field::Calculated(_, cost) = self.maze.field(x, y) {
+                    self.converging = Some(Convergence::new(Some(cost)));
+                    return Status::Running;
+                }
+            }
+        }
+
+        let status = exit_status(self.maze.field(x, y));
+        self.status = Some(status);
+        self.observer.finished(&self.maze);
+        status
+    }
+
+    fn maze(&self) -> &Maze<S> {
+        &self.maze
+    }
+
+    fn bound(&self) -> Bound {
+        let (x, y) = self.exit;
+        match (&self.converging, self.maze.field(x, y)) {
+            // Exit cost is already final while converging
+            (Some(_), Field::Calculated(_, cost)) => Bound {
+                lower: cost,
+                upper: Some(cost),
+            },
+            (_, exit) => bound(exit, self.best),
+        }
+    }
+}
+
+/// Implementation of cache blocked flood search algorithm - every step is a round of locally
+/// stabilized tiles instead of single iteration (or iteration of convergence, once exit cost is
+/// known). Finds the same exit cost as `flood`.
+///
+/// Every round and field update is reported to given observer (`()` for no-op). Calculation is
+/// stopped early if any of limits is exceeded, which is reflected in returned status.
+pub fn tiled_flood<S: Storage>(
+    maze: Maze<S>,
+    x: usize,
+    y: usize,
+    observer: &mut impl SolverObserver,
+    limits: &Limits,
+) -> (Maze<S>, Status) {
+    let mut flood = TiledFlood::with_observer(maze, x, y, observer);
+    let status = flood.run_limited(limits);
+    (flood.into_maze(), status)
+}
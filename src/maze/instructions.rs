@@ -0,0 +1,126 @@
+//! Path as a program for a robot
+//!
+//! Robot stands on the first field of the path with some heading, and can only go forward by
+//! given number of fields, or turn in place - by 90 degrees, or turn around. Fields are neighbours
+//! the same way as for solvers, so going right from the last column of the maze goes to the first
+//! column of the next row (and going left from the first column - to the last column of the
+//! previous row).
+//!
+//! Every turn is a single instruction, and turns are exactly what the cost counts - except turns
+//! before the first forward, as the start is approached from any direction. So program of a path
+//! has as many turns after its first forward as the cost of the path.
+//!
+//! Heading is given as direction from which the next field is approached when going forward (the
+//! same way as solvers store it) - so robot entering the maze from its left edge has heading
+//! `Dir::LEFT`.
+
+use super::{Dir, Maze, Storage};
+
+/// Single step of robot program
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Instruction {
+    /// Goes forward by given number of fields
+    Forward(usize),
+    /// Turns left in place
+    Left,
+    /// Turns right in place
+    Right,
+    /// Turns back in place
+    Around,
+}
+
+impl Instruction {
+    /// Parses single instruction in the same form it is displayed (`F3`, `L`, `R` or `U`)
+    pub fn parse(token: &str) -> Option<Self> {
+        match token {
+            "L" => Some(Instruction::Left),
+            "R" => Some(Instruction::Right),
+            "U" => Some(Instruction::Around),
+            _ => token
+                .strip_prefix('F')?
+                .parse()
+                .ok()
+                .map(Instruction::Forward),
+        }
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Instruction::Forward(fields) => write!(f, "F{}", fields),
+            Instruction::Left => f.write_str("L"),
+            Instruction::Right => f.write_str("R"),
+            Instruction::Around => f.write_str("U"),
+        }
+    }
+}
+
+/// Converts path (coordinates of following fields, as `Maze::path` gives it) into program of
+/// robot with given starting heading. Path has to go through neighbouring fields of the maze.
+pub fn instructions<S: Storage>(
+    maze: &Maze<S>,
+    path: &[(usize, usize)],
+    heading: Dir,
+) -> Vec<Instruction> {
+    let mut program = vec![];
+    let mut heading = heading;
+
+    for step in path.windows(2) {
+        let (prev, next) = (
+            maze.idx(step[0].0, step[0].1),
+            maze.idx(step[1].0, step[1].1),
+        );
+        let dir = maze
+            .approach(prev, next)
+            .expect("Path goes through not neighbouring fields");
+        if dir != heading {
+            if dir == heading.left() {
+                program.push(Instruction::Left);
+            } else if dir == heading.right() {
+                program.push(Instruction::Right);
+            } else {
+                program.push(Instruction::Around);
+            }
+            heading = dir;
+        }
+
+        match program.last_mut() {
+            Some(Instruction::Forward(fields)) => *fields += 1,
+            _ => program.push(Instruction::Forward(1)),
+        }
+    }
+
+    program
+}
+
+/// Runs program of robot starting on given field with given heading, giving coordinates of every
+/// field it goes through (including the starting one). Walls are not checked, but if the robot
+/// leaves the maze, the error is the number of the step which does it.
+pub fn execute<S: Storage>(
+    maze: &Maze<S>,
+    program: &[Instruction],
+    start: (usize, usize),
+    heading: Dir,
+) -> Result<Vec<(usize, usize)>, usize> {
+    let mut path = vec![maze.idx(start.0, start.1)];
+    let mut heading = heading;
+
+    for instruction in program {
+        match instruction {
+            Instruction::Left => heading = heading.left(),
+            Instruction::Right => heading = heading.right(),
+            Instruction::Around => heading = heading.left().left(),
+            Instruction::Forward(fields) => {
+                for _ in 0..*fields {
+                    let next = maze
+                        .forward(path[path.len() - 1], heading)
+                        .ok_or(path.len())?;
+                    path.push(next);
+                }
+            }
+        }
+    }
+
+    Ok(path.into_iter().map(|idx| maze.coords(idx)).collect())
+}
@@ -0,0 +1,150 @@
+//! Hooks for observing solvers progress
+//!
+//! Solvers are generic over observer, and all of its methods are no-op by default, so not
+//! observed calculation (with `()` as observer) costs nothing - all calls are optimized out.
+
+use super::{Maze, Storage};
+use std::collections::HashMap;
+
+/// Receiver of events emitted by solvers
+pub trait SolverObserver {
+    /// Flood iteration is about to start; Maze is in the state after previous iteration
+    fn iteration_started<S: Storage>(&mut self, _maze: &Maze<S>) {}
+
+    /// Field changed its cost (or set of directions it is approached from with the same cost)
+    fn cell_updated(&mut self, _idx: usize, _old: Option<usize>, _new: usize) {}
+
+    /// A* node is taken from queue to be expanded; Maze is in the state after previous expansion
+    fn node_popped<S: Storage>(&mut self, _maze: &Maze<S>, _idx: usize, _priority: usize) {}
+
+    /// A* node is put into queue
+    fn node_pushed(&mut self, _idx: usize, _priority: usize) {}
+
+    /// Calculation is finished
+    fn finished<S: Storage>(&mut self, _maze: &Maze<S>) {}
+}
+
+impl SolverObserver for () {}
+
+impl<O: SolverObserver + ?Sized> SolverObserver for &mut O {
+    fn iteration_started<S: Storage>(&mut self, maze: &Maze<S>) {
+        (**self).iteration_started(maze)
+    }
+
+    fn cell_updated(&mut self, idx: usize, old: Option<usize>, new: usize) {
+        (**self).cell_updated(idx, old, new)
+    }
+
+    fn node_popped<S: Storage>(&mut self, maze: &Maze<S>, idx: usize, priority: usize) {
+        (**self).node_popped(maze, idx, priority)
+    }
+
+    fn node_pushed(&mut self, idx: usize, priority: usize) {
+        (**self).node_pushed(idx, priority)
+    }
+
+    fn finished<S: Storage>(&mut self, maze: &Maze<S>) {
+        (**self).finished(maze)
+    }
+}
+
+impl<O: SolverObserver> SolverObserver for Option<O> {
+    fn iteration_started<S: Storage>(&mut self, maze: &Maze<S>) {
+        if let Some(observer) = self {
+            observer.iteration_started(maze)
+        }
+    }
+
+    fn cell_updated(&mut self, idx: usize, old: Option<usize>, new: usize) {
+        if let Some(observer) = self {
+            observer.cell_updated(idx, old, new)
+        }
+    }
+
+    fn node_popped<S: Storage>(&mut self, maze: &Maze<S>, idx: usize, priority: usize) {
+        if let Some(observer) = self {
+            observer.node_popped(maze, idx, priority)
+        }
+    }
+
+    fn node_pushed(&mut self, idx: usize, priority: usize) {
+        if let Some(observer) = self {
+            observer.node_pushed(idx, priority)
+        }
+    }
+
+    fn finished<S: Storage>(&mut self, maze: &Maze<S>) {
+        if let Some(observer) = self {
+            observer.finished(maze)
+        }
+    }
+}
+
+impl<A: SolverObserver, B: SolverObserver> SolverObserver for (A, B) {
+    fn iteration_started<S: Storage>(&mut self, maze: &Maze<S>) {
+        self.0.iteration_started(maze);
+        self.1.iteration_started(maze);
+    }
+
+    fn cell_updated(&mut self, idx: usize, old: Option<usize>, new: usize) {
+        self.0.cell_updated(idx, old, new);
+        self.1.cell_updated(idx, old, new);
+    }
+
+    fn node_popped<S: Storage>(&mut self, maze: &Maze<S>, idx: usize, priority: usize) {
+        self.0.node_popped(maze, idx, priority);
+        self.1.node_popped(maze, idx, priority);
+    }
+
+    fn node_pushed(&mut self, idx: usize, priority: usize) {
+        self.0.node_pushed(idx, priority);
+        self.1.node_pushed(idx, priority);
+    }
+
+    fn finished<S: Storage>(&mut self, maze: &Maze<S>) {
+        self.0.finished(maze);
+        self.1.finished(maze);
+    }
+}
+
+/// Helper tracking frontier of the algorithm basing on events - fields updated since last step
+/// (flood), and fields waiting in the queue (A*).
+#[derive(Default)]
+pub struct Frontier {
+    updated: Vec<usize>,
+    /// Number of queue entries for every field - the same field can be queued multiple times
+    queued: HashMap<usize, usize>,
+}
+
+impl Frontier {
+    pub fn updated(&mut self, idx: usize) {
+        self.updated.push(idx);
+    }
+
+    pub fn pushed(&mut self, idx: usize) {
+        *self.queued.entry(idx).or_default() += 1;
+    }
+
+    pub fn popped(&mut self, idx: usize) {
+        if let Some(cnt) = self.queued.get_mut(&idx) {
+            *cnt -= 1;
+            if *cnt == 0 {
+                self.queued.remove(&idx);
+            }
+        }
+    }
+
+    /// If anything was updated since last step
+    pub fn changed(&self) -> bool {
+        !self.updated.is_empty()
+    }
+
+    /// Finishes the step, returning current frontier
+    pub fn step(&mut self) -> Vec<usize> {
+        let mut frontier = std::mem::take(&mut self.updated);
+        frontier.extend(self.queued.keys().copied());
+        frontier.sort_unstable();
+        frontier.dedup();
+        frontier
+    }
+}
@@ -0,0 +1,369 @@
+//! Analysis of all optimal paths
+//!
+//! Every calculated field keeps all directions from which its best cost is achievable, so the
+//! solved maze contains all optimal paths. The field itself is not enough to follow them, as
+//! the cost of going out of the field depends on the direction it was entered from - so the
+//! paths are followed over states (field, direction it is approached from). Cost of the state is
+//! cost of the neighbour it is approached from, plus one if this neighbour is not approached from
+//! the same direction (turn). State is on an optimal path if there is a state before it which
+//! gives exactly its cost - this is the DAG all optimal paths are walked on, backward from exit.
+//!
+//! Solvers stop as soon as the exit cannot be any cheaper, so ties may be missing in the solved
+//! maze - it is converged up to the exit cost before analysis, so nothing is lost. Fields more
+//! expensive than the exit are never on optimal path, so the rest of the maze is not flooded.
+//! If converging finds the exit cheaper than solver did (solver finished with not optimal cost,
+//! or it was stopped by limits), the maze doesn't tell its optimal paths, and analysis is refused.
+//!
+//! Paths can be counted, enumerated one by one, or sampled - uniformly, as every predecessor is
+//! picked with probability proportional to number of paths going through it.
+//!
+//! Walking the DAG forward from start gives number of optimal paths to every state, walking it
+//! in reverse from exit gives number of optimal paths from every state to exit - and together
+//! number of optimal paths through every field. Fields with any such path are where optimal
+//! solution can go, fields with all of them are its chokepoints.
+
+use super::flood::converge;
+use super::{Dir, Field, Mark, Maze, Storage};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+
+/// Field index with direction it is approached from
+type State = (usize, Dir);
+
+/// Optimal paths DAG of converged maze
+struct Dag<S: Storage> {
+    maze: Maze<S>,
+    start: usize,
+    exit: usize,
+}
+
+impl<S: Storage> Dag<S> {
+    /// DAG of solved maze converged up to the exit cost; `None` if the exit cost is not optimal
+    fn new(maze: &Maze<S>, start: (usize, usize), exit: (usize, usize)) -> Option<Self> {
+        let mut maze = maze.clone();
+        let start = maze.idx(start.0, start.1);
+        let exit = maze.idx(exit.0, exit.1);
+
+        if let Field::Calculated(_, cost) = maze.get(exit) {
+            converge(&mut maze, Some(cost));
+            if !matches!(maze.get(exit), Field::Calculated(_, converged) if converged == cost) {
+                return None;
+            }
+        }
+
+        Some(Self { maze, start, exit })
+    }
+
+    /// The best cost of reaching given state (`None` if it is not reachable at all)
+    fn cost(&self, (idx, dir): State) -> Option<usize> {
+        if idx == self.start {
+            return Some(0);
+        }
+
+        match (self.maze.get(idx), self.maze.in_dir(idx, dir)) {
+            (Field::Calculated(_, _), Field::Calculated(pdir, cost)) => {
+                Some(cost + !pdir.has_all(dir) as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// Final states of all optimal paths
+    fn exits(&self) -> Vec<State> {
+        match self.maze.get(self.exit) {
+            Field::Calculated(dirs, _) => dirs.iter().map(|dir| (self.exit, dir)).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// States directly before given one on any optimal path; Start has none
+    fn predecessors(&self, state: State) -> Vec<State> {
+        let (idx, dir) = state;
+        let cost = match self.cost(state) {
+            Some(cost) if idx != self.start => cost,
+            _ => return vec![],
+        };
+
+        let prev = self.maze.in_dir_idx(idx, dir);
+        Dir::ANY
+            .iter()
+            .map(|pdir| (prev, pdir))
+            .filter(|pstate| {
+                self.cost(*pstate)
+                    .is_some_and(|pcost| pcost + (pstate.1 != dir) as usize == cost)
+            })
+            .collect()
+    }
+
+    /// Coordinates of fields of path given as states from exit to start
+    fn coords(&self, states: &[State]) -> Vec<(usize, usize)> {
+        states
+            .iter()
+            .rev()
+            .map(|(idx, _)| self.maze.coords(*idx))
+            .collect()
+    }
+
+    /// Number of optimal paths from start to every state on any optimal path to exit, with all
+    /// those states in topological order (every state after all its predecessors)
+    fn counts(&self) -> (HashMap<State, BigUint>, Vec<State>) {
+        let mut counts = HashMap::new();
+        let mut order = vec![];
+
+        // Paths may be way too long for recursion, so DAG is walked with explicit stack; State is
+        // pushed again (with its predecessors) before it is counted
+        let mut stack: Vec<_> = self
+            .exits()
+            .into_iter()
+            .map(|state| (state, false))
+            .collect();
+        while let Some((state, ready)) = stack.pop() {
+            if counts.contains_key(&state) {
+                continue;
+            }
+
+            let predecessors = self.predecessors(state);
+            if ready {
+                let count = if state.0 == self.start {
+                    BigUint::from(1u32)
+                } else {
+                    predecessors.iter().map(|pstate| &counts[pstate]).sum()
+                };
+                counts.insert(state, count);
+                order.push(state);
+            } else {
+                stack.push((state, true));
+                stack.extend(
+                    predecessors
+                        .into_iter()
+                        .filter(|pstate| !counts.contains_key(pstate))
+                        .map(|pstate| (pstate, false)),
+                );
+            }
+        }
+
+        (counts, order)
+    }
+}
+
+/// Lazy iterator over all optimal paths, created with `optimal_paths`
+///
+/// Paths are found with depth first search backward from exit, so only single path is kept at a
+/// time - but all alternatives on the way, so memory is proportional to the path length.
+pub struct OptimalPaths<S: Storage> {
+    dag: Dag<S>,
+    /// States of currently followed path, from exit backward
+    path: Vec<State>,
+    /// Not yet followed alternatives for every state of path
+    choices: Vec<Vec<State>>,
+}
+
+impl<S: Storage> Iterator for OptimalPaths<S> {
+    type Item = Vec<(usize, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let depth = self.choices.len().checked_sub(1)?;
+            let state = match self.choices[depth].pop() {
+                Some(state) => state,
+                None => {
+                    self.choices.pop();
+                    continue;
+                }
+            };
+
+            self.path.truncate(depth);
+            self.path.push(state);
+            if state.0 == self.dag.start {
+                return Some(self.dag.coords(&self.path));
+            }
+            self.choices.push(self.dag.predecessors(state));
+        }
+    }
+}
+
+/// Fields on optimal paths, created with `critical_cells`
+pub struct Critical {
+    w: usize,
+    mask: Vec<Mark>,
+}
+
+impl Critical {
+    /// Mark of every field of the maze, by index - can be passed directly to `render_mask`
+    pub fn mask(&self) -> &[Mark] {
+        &self.mask
+    }
+
+    /// Coordinates of fields which mark matches given predicate
+    fn marked(&self, predicate: impl Fn(Mark) -> bool) -> Vec<(usize, usize)> {
+        self.mask
+            .iter()
+            .enumerate()
+            .filter(|(_, mark)| predicate(**mark))
+            .map(|(idx, _)| (idx % self.w, idx / self.w))
+            .collect()
+    }
+
+    /// Coordinates of all fields on at least one optimal path (including chokepoints)
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        self.marked(|mark| mark != Mark::None)
+    }
+
+    /// Coordinates of all fields on every optimal path
+    pub fn chokepoints(&self) -> Vec<(usize, usize)> {
+        self.marked(|mark| mark == Mark::Chokepoint)
+    }
+}
+
+/// Minimal seeded random generator (SplitMix64) - sampling only needs to be reproducible, not
+/// cryptographically strong
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly random number below given (non zero) one; Random bits are rejected until they
+    /// give number in range, so there is no bias
+    fn below(&mut self, max: &BigUint) -> BigUint {
+        let bits = max.bits() as usize;
+        loop {
+            let mut bytes: Vec<_> = (0..bits.div_ceil(64))
+                .flat_map(|_| self.next().to_le_bytes())
+                .collect();
+            bytes.truncate(bits.div_ceil(8));
+            if let Some(last) = bytes.last_mut() {
+                *last &= 0xff >> ((8 - bits % 8) % 8);
+            }
+
+            let number = BigUint::from_bytes_le(&bytes);
+            if &number < max {
+                return number;
+            }
+        }
+    }
+
+    /// Picks one of states with probability proportional to its count
+    fn pick(&mut self, states: &[State], counts: &HashMap<State, BigUint>) -> State {
+        let total: BigUint = states.iter().map(|state| &counts[state]).sum();
+        let mut number = self.below(&total);
+        for state in states {
+            let count = &counts[state];
+            if &number < count {
+                return *state;
+            }
+            number -= count;
+        }
+        unreachable!("Random number below total count")
+    }
+}
+
+/// Counts all distinct optimal paths from `start` to `exit` in solved maze (zero if exit is not
+/// calculated, `None` if its cost is not optimal). Paths are distinct if they go through different
+/// fields - the count grows exponentially on open areas, so it is never truncated.
+pub fn count_optimal_paths<S: Storage>(
+    maze: &Maze<S>,
+    start: (usize, usize),
+    exit: (usize, usize),
+) -> Option<BigUint> {
+    let dag = Dag::new(maze, start, exit)?;
+    let (counts, _) = dag.counts();
+    Some(dag.exits().iter().map(|state| &counts[state]).sum())
+}
+
+/// Iterates over all distinct optimal paths from `start` to `exit` in solved maze (none if exit
+/// is not calculated, `None` if its cost is not optimal). Every path is given as coordinates of
+/// fields from start to exit, the same way as `Maze::path` gives it.
+pub fn optimal_paths<S: Storage>(
+    maze: &Maze<S>,
+    start: (usize, usize),
+    exit: (usize, usize),
+) -> Option<OptimalPaths<S>> {
+    let dag = Dag::new(maze, start, exit)?;
+    let choices = vec![dag.exits()];
+
+    Some(OptimalPaths {
+        dag,
+        path: vec![],
+        choices,
+    })
+}
+
+/// Picks one of all distinct optimal paths from `start` to `exit` in solved maze, every of them
+/// with the same probability (`None` if exit is not calculated, or its cost is not optimal). The
+/// same seed always gives the same path.
+pub fn sample_optimal_path<S: Storage>(
+    maze: &Maze<S>,
+    start: (usize, usize),
+    exit: (usize, usize),
+    seed: u64,
+) -> Option<Vec<(usize, usize)>> {
+    let dag = Dag::new(maze, start, exit)?;
+    let (counts, _) = dag.counts();
+    let mut random = Random(seed);
+
+    let exits = dag.exits();
+    if exits.is_empty() {
+        return None;
+    }
+
+    let mut path = vec![random.pick(&exits, &counts)];
+    while let Some(state) = path.last().filter(|state| state.0 != dag.start) {
+        let predecessors = dag.predecessors(*state);
+        path.push(random.pick(&predecessors, &counts));
+    }
+
+    Some(dag.coords(&path))
+}
+
+/// Finds all fields on at least one optimal path from `start` to `exit` in solved maze, and
+/// which of them are on every optimal path (all marks are `Mark::None` if exit is not
+/// calculated, `None` if its cost is not optimal)
+pub fn critical_cells<S: Storage>(
+    maze: &Maze<S>,
+    start: (usize, usize),
+    exit: (usize, usize),
+) -> Option<Critical> {
+    let dag = Dag::new(maze, start, exit)?;
+    let (forward, order) = dag.counts();
+
+    // Reverse walk - every state is visited after all states it is predecessor of
+    let mut reverse: HashMap<State, BigUint> = dag
+        .exits()
+        .into_iter()
+        .map(|state| (state, BigUint::from(1u32)))
+        .collect();
+    for state in order.iter().rev() {
+        let count = reverse.get(state).cloned().unwrap_or_default();
+        for pstate in dag.predecessors(*state) {
+            *reverse.entry(pstate).or_default() += &count;
+        }
+    }
+
+    // Optimal path never visits the same field twice (it would be cheaper to turn directly on
+    // the first visit), so paths through field are just sum over its states
+    let mut through: HashMap<usize, BigUint> = HashMap::new();
+    for state in &order {
+        *through.entry(state.0).or_default() += &forward[state] * &reverse[state];
+    }
+
+    let total: BigUint = dag.exits().iter().map(|state| &forward[state]).sum();
+    let mut mask = vec![Mark::None; dag.maze.maze.len()];
+    for (idx, count) in through {
+        mask[idx] = if count == total {
+            Mark::Chokepoint
+        } else {
+            Mark::Optimal
+        };
+    }
+
+    Some(Critical {
+        w: dag.maze.w,
+        mask,
+    })
+}
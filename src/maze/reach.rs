@@ -0,0 +1,212 @@
+//! Bitboard reachability flood
+//!
+//! Often it is enough to know if exit is reachable at all, and this doesn't need any costs. Here
+//! maze is packed into bitboards - every row is a number of `u64` words, with bit set for every
+//! empty field (open mask), and for every field reachable from the start (visited mask). Visited
+//! fields are expanded in the whole row at once with shift-and-mask fill, and then between rows
+//! with simple `or` of neighbouring rows - so single word handles 64 fields at once.
+//!
+//! Rows are split into stripes calculated in parallel. Every stripe is expanded locally until it
+//! is stable, taking rows around it from the previous round, and rounds are repeated until no
+//! stripe changed. Reachable fields are exactly those which `flood` calculates, including the
+//! wrapping of right edge into the next row (so the last field in a row is reachable from the
+//! first field of the next one, but not the other way).
+
+use super::{Field, Maze, Storage};
+use rayon::prelude::*;
+
+/// Number of rows in a stripe calculated by single task
+const STRIPE: usize = 64;
+
+/// Fields reachable from any calculated field of the maze
+pub struct Reach {
+    w: usize,
+    /// Number of words in every row
+    words: usize,
+    visited: Vec<u64>,
+}
+
+impl Reach {
+    /// If field with given coordinates is reachable
+    pub fn reachable(&self, x: usize, y: usize) -> bool {
+        x < self.w
+            && self
+                .visited
+                .get(y * self.words + x / 64)
+                .is_some_and(|word| word >> (x % 64) & 1 == 1)
+    }
+
+    /// Number of reachable fields
+    pub fn count(&self) -> usize {
+        self.visited
+            .par_iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+/// Fills set bits of `g` towards higher bits through set bits of `p`
+fn fill_up(mut g: u64, mut p: u64) -> u64 {
+    g &= p;
+    for shift in [1, 2, 4, 8, 16, 32] {
+        g |= p & (g << shift);
+        p &= p << shift;
+    }
+    g
+}
+
+/// Fills set bits of `g` towards lower bits through set bits of `p`
+fn fill_down(mut g: u64, mut p: u64) -> u64 {
+    g &= p;
+    for shift in [1, 2, 4, 8, 16, 32] {
+        g |= p & (g >> shift);
+        p &= p >> shift;
+    }
+    g
+}
+
+/// Expands visited fields along the whole row - first to the right, then back to the left, so
+/// every open run containing any visited field is visited entirely
+fn fill_row(row: &mut [u64], open: &[u64]) {
+    let mut carry = 0;
+    for (word, open) in row.iter_mut().zip(open) {
+        *word = fill_up(*word | carry, *open);
+        carry = *word >> 63;
+    }
+
+    let mut carry = 0;
+    for (word, open) in row.iter_mut().zip(open).rev() {
+        *word = fill_down(*word | carry, *open);
+        carry = (*word & 1) << 63;
+    }
+}
+
+/// Single stripe of rows with everything needed to expand it
+struct Stripe<'a> {
+    w: usize,
+    words: usize,
+    visited: &'a mut [u64],
+    open: &'a [u64],
+    /// Visited row directly above stripe from previous round (if any)
+    above: Option<&'a [u64]>,
+    /// Visited row directly below stripe from previous round (if any)
+    below: Option<&'a [u64]>,
+}
+
+impl Stripe<'_> {
+    fn rows(&self) -> usize {
+        self.visited.len() / self.words
+    }
+
+    fn row(&self, row: usize) -> &[u64] {
+        &self.visited[row * self.words..(row + 1) * self.words]
+    }
+
+    /// Row above or below given one, taking it from outside of stripe if needed
+    fn neighbour(&self, row: usize, up: bool) -> Option<&[u64]> {
+        match (up, row) {
+            (true, 0) => self.above,
+            (true, row) => Some(self.row(row - 1)),
+            (false, row) if row + 1 == self.rows() => self.below,
+            (false, row) => Some(self.row(row + 1)),
+        }
+    }
+
+    /// Expands single row from rows around it, returns if it changed
+    fn update(&mut self, row: usize, scratch: &mut [u64]) -> bool {
+        scratch.copy_from_slice(self.row(row));
+
+        for neighbour in [self.neighbour(row, true), self.neighbour(row, false)] {
+            for (word, from) in scratch.iter_mut().zip(neighbour.unwrap_or_default()) {
+                *word |= from;
+            }
+        }
+
+        // Last field of the row is approached from the first field of the next row
+        if self
+            .neighbour(row, false)
+            .is_some_and(|next| next[0] & 1 == 1)
+        {
+            scratch[(self.w - 1) / 64] |= 1 << ((self.w - 1) % 64);
+        }
+
+        let open = &self.open[row * self.words..(row + 1) * self.words];
+        for (word, open) in scratch.iter_mut().zip(open) {
+            *word &= open;
+        }
+        fill_row(scratch, open);
+
+        let current = &mut self.visited[row * self.words..(row + 1) * self.words];
+        let changed = current != scratch;
+        current.copy_from_slice(scratch);
+        changed
+    }
+
+    /// Expands stripe until it is stable, returns if anything changed
+    fn settle(&mut self) -> bool {
+        let mut scratch = vec![0; self.words];
+        let mut changed = false;
+
+        loop {
+            let mut sweep = false;
+            for row in (0..self.rows()).chain((0..self.rows()).rev()) {
+                sweep |= self.update(row, &mut scratch);
+            }
+
+            if !sweep {
+                return changed;
+            }
+            changed = true;
+        }
+    }
+}
+
+/// Finds all fields reachable from any calculated field of the maze (so normally from the start)
+pub fn reach<S: Storage>(maze: &Maze<S>) -> Reach {
+    let w = maze.w;
+    let words = w.div_ceil(64);
+    let h = maze.height();
+
+    let mut open = vec![0; h * words];
+    let mut visited = vec![0; h * words];
+    for (idx, field) in maze.fields().enumerate() {
+        let (x, y) = maze.coords(idx);
+        let bit = 1 << (x % 64);
+        match field {
+            Field::Wall => continue,
+            Field::Calculated(_, _) => visited[y * words + x / 64] |= bit,
+            Field::Empty => (),
+        }
+        open[y * words + x / 64] |= bit;
+    }
+
+    let len = STRIPE * words;
+    loop {
+        // Rows around every stripe are taken from previous round, so stripes are independent
+        let previous = visited.clone();
+        let changed = visited
+            .par_chunks_mut(len)
+            .zip(open.par_chunks(len))
+            .enumerate()
+            .map(|(stripe, (visited, open))| {
+                let first = stripe * len;
+                let last = first + visited.len();
+                Stripe {
+                    w,
+                    words,
+                    visited,
+                    open,
+                    above: first
+                        .checked_sub(words)
+                        .map(|above| &previous[above..first]),
+                    below: previous.get(last..last + words),
+                }
+                .settle()
+            })
+            .reduce(|| false, |a, b| a | b);
+
+        if !changed {
+            return Reach { w, words, visited };
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Plain text rendering of mazes with unicode box drawing characters
+//!
+//! Unlike visualization this doesn't use any terminal escape codes, so result can be just copied
+//! anywhere. Walls are drawn with heavy lines connected to neighbouring walls, path is drawn with
+//! light lines, so its turns are visible. Instead of path, mask of marked fields can be drawn with
+//! shades.
+
+use super::{Field, Maze, Storage};
+
+/// Connection bits used to pick box drawing character
+const UP: u8 = 1;
+const RIGHT: u8 = 2;
+const DOWN: u8 = 4;
+const LEFT: u8 = 8;
+
+/// Heavy lines indexed by connection bits
+const WALLS: [char; 16] = [
+    '■', '╹', '╺', '┗', '╻', '┃', '┏', '┣', '╸', '┛', '━', '┻', '┓', '┫', '┳', '╋',
+];
+
+/// Light lines indexed by connection bits; Path never has more than two connections
+const PATH: [char; 16] = [
+    '•', '│', '─', '└', '│', '│', '┌', '│', '─', '┘', '─', '─', '┐', '─', '─', '─',
+];
+
+/// Shades of marked fields indexed by `Mark`
+const MARKS: [Option<char>; 3] = [None, Some('░'), Some('▓')];
+
+/// Marking of single field in mask
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mark {
+    None,
+    /// Field is on some of optimal paths
+    Optimal,
+    /// Field is on every optimal path
+    Chokepoint,
+}
+
+/// Rendering style
+#[derive(Clone, Copy, Debug)]
+pub enum Style {
+    /// Every field is single character with lines connecting neighbours
+    Box,
+    /// Two rows of maze per line drawn with half block characters
+    Compact,
+}
+
+/// Renders maze with given path marked on it
+pub fn render<S: Storage>(maze: &Maze<S>, path: &[(usize, usize)], style: Style) -> String {
+    let connections = path_connections(maze, path);
+    let overlay = |idx: usize| connections[idx].map(|bits| PATH[bits as usize]);
+
+    match style {
+        Style::Box => boxes(maze, overlay),
+        Style::Compact => compact(maze, overlay),
+    }
+}
+
+/// Renders maze with given mask (with mark for every field) shaded on it; Compact style doesn't
+/// distinguish marks
+pub fn render_mask<S: Storage>(maze: &Maze<S>, mask: &[Mark], style: Style) -> String {
+    let overlay = |idx: usize| mask.get(idx).and_then(|mark| MARKS[*mark as usize]);
+
+    match style {
+        Style::Box => boxes(maze, overlay),
+        Style::Compact => compact(maze, overlay),
+    }
+}
+
+/// For every field returns connections of the path going through it (`None` if field is not
+/// on path). Path ends are treated as going straight, so entrance and exit are drawn as lines
+/// leading outside.
+fn path_connections<S: Storage>(maze: &Maze<S>, path: &[(usize, usize)]) -> Vec<Option<u8>> {
+    let mut connections = vec![None; maze.maze.len()];
+
+    let link = |(fx, fy): (usize, usize), (tx, ty): (usize, usize)| {
+        if tx > fx {
+            RIGHT
+        } else if tx < fx {
+            LEFT
+        } else if ty > fy {
+            DOWN
+        } else if ty < fy {
+            UP
+        } else {
+            0
+        }
+    };
+
+    for (i, (x, y)) in path.iter().copied().enumerate() {
+        let prev = i.checked_sub(1).map_or(0, |i| link((x, y), path[i]));
+        let next = path.get(i + 1).map_or(0, |next| link((x, y), *next));
+
+        let mut bits = prev | next;
+        if path.len() > 1 && (i == 0 || i == path.len() - 1) {
+            // Straighten ends - opposite connection is 2 bits away
+            bits |= ((bits << 2) | (bits >> 2)) & 0xf;
+        }
+
+        if let Some(c) = connections.get_mut(maze.idx(x, y)) {
+            *c = Some(bits);
+        }
+    }
+
+    connections
+}
+
+/// Renders every field as single character, non wall fields with character given by overlay
+fn boxes<S: Storage>(maze: &Maze<S>, overlay: impl Fn(usize) -> Option<char>) -> String {
+    let is_wall = |x: usize, y: usize| matches!(maze.field(x, y), Field::Wall);
+    let mut result = String::new();
+
+    for (idx, field) in maze.fields().enumerate() {
+        let (x, y) = maze.coords(idx);
+
+        let c = match (field, overlay(idx)) {
+            (Field::Wall, _) => {
+                // Wrapping on edges gives index out of maze, which is treated as a wall - it is
+                // not wanted here, as walls would be connected to nothing
+                let up = y > 0 && is_wall(x, y - 1);
+                let down = maze.idx(x, y + 1) < maze.maze.len() && is_wall(x, y + 1);
+                let left = x > 0 && is_wall(x - 1, y);
+                let right = x + 1 < maze.w && is_wall(x + 1, y);
+
+                WALLS[(up as usize)
+                    | (right as usize) << 1
+                    | (down as usize) << 2
+                    | (left as usize) << 3]
+            }
+            (_, Some(c)) => c,
+            _ => ' ',
+        };
+
+        result.push(c);
+        if x + 1 == maze.w {
+            result.push('\n');
+        }
+    }
+
+    result
+}
+
+/// Renders two rows of fields as single line. Walls fills whole half of character, fields with
+/// any overlay (path) only its left quarter.
+fn compact<S: Storage>(maze: &Maze<S>, overlay: impl Fn(usize) -> Option<char>) -> String {
+    let mut result = String::new();
+
+    #[derive(Clone, Copy)]
+    enum Half {
+        Empty,
+        Wall,
+        Path,
+    }
+
+    let field = |idx: usize| Some(maze.get(idx)).filter(|_| idx < maze.maze.len());
+    let half = |idx: usize| match field(idx) {
+        Some(Field::Wall) => Half::Wall,
+        Some(_) if overlay(idx).is_some() => Half::Path,
+        _ => Half::Empty,
+    };
+
+    for y in (0..maze.height()).step_by(2) {
+        for x in 0..maze.w {
+            let c = match (half(maze.idx(x, y)), half(maze.idx(x, y + 1))) {
+                (Half::Empty, Half::Empty) => ' ',
+                (Half::Wall, Half::Empty) => '▀',
+                (Half::Empty, Half::Wall) => '▄',
+                (Half::Wall, Half::Wall) => '█',
+                (Half::Path, Half::Empty) => '▘',
+                (Half::Empty, Half::Path) => '▖',
+                (Half::Path, Half::Path) => '▌',
+                (Half::Wall, Half::Path) => '▛',
+                (Half::Path, Half::Wall) => '▙',
+            };
+            result.push(c);
+        }
+        result.push('\n');
+    }
+
+    result
+}
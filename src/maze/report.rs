@@ -0,0 +1,240 @@
+//! Self contained HTML report of the calculation
+//!
+//! Every step of algorithm (flood iteration, or A* expansion) is recorded as a difference from the
+//! previous one (only fields which changed their cost, and the frontier), and then all of it is
+//! embedded directly into single HTML file with small script replaying it on canvas. There are no
+//! external assets, so file can be just attached anywhere.
+
+use super::observer::Frontier;
+use super::{Field, Maze, SolverObserver, Storage};
+use std::fmt::Write;
+
+/// Single recorded step of algorithm
+struct Step {
+    /// Fields with changed cost as (index, new cost)
+    changed: Vec<(usize, usize)>,
+    /// Fields on the frontier after this step
+    frontier: Vec<usize>,
+}
+
+/// Records algorithm steps
+pub struct Recorder {
+    /// Costs of fields as they were on initial maze
+    initial: Vec<Option<usize>>,
+    steps: Vec<Step>,
+    /// Changes in currently recorded step
+    changed: Vec<(usize, usize)>,
+    frontier: Frontier,
+}
+
+impl Recorder {
+    pub fn new<S: Storage>(maze: &Maze<S>) -> Self {
+        let initial = maze
+            .fields()
+            .map(|field| match field {
+                Field::Calculated(_, cost) => Some(cost),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            initial,
+            steps: vec![],
+            changed: vec![],
+            frontier: Frontier::default(),
+        }
+    }
+
+    /// Finishes currently recorded step if anything changed in it
+    fn step(&mut self) {
+        if self.frontier.changed() {
+            self.steps.push(Step {
+                changed: std::mem::take(&mut self.changed),
+                frontier: self.frontier.step(),
+            });
+        }
+    }
+
+    /// Generates HTML report with all recorded steps, and given path on final maze
+    pub fn html<S: Storage>(&self, maze: &Maze<S>, path: &[(usize, usize)]) -> String {
+        let h = maze.height();
+
+        let walls: String = maze
+            .fields()
+            .map(|field| if let Field::Wall = field { '1' } else { '0' })
+            .collect();
+
+        let initial = Self::js_list(
+            self.initial
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, cost)| cost.map(|cost| format!("{},{}", idx, cost))),
+        );
+
+        let steps = Self::js_list(self.steps.iter().map(|step| {
+            let changed = Self::js_list(
+                step.changed
+                    .iter()
+                    .map(|(idx, cost)| format!("{},{}", idx, cost)),
+            );
+            let frontier = Self::js_list(step.frontier.iter().map(|idx| idx.to_string()));
+            format!("[{},{}]", changed, frontier)
+        }));
+
+        let path = Self::js_list(path.iter().map(|(x, y)| format!("{},{}", x, y)));
+
+        let mut data = String::new();
+        writeln!(data, "const W = {}, H = {};", maze.w, h).unwrap();
+        writeln!(data, "const WALLS = \"{}\";", walls).unwrap();
+        writeln!(data, "const INITIAL = {};", initial).unwrap();
+        writeln!(data, "const STEPS = {};", steps).unwrap();
+        writeln!(data, "const PATH = {};", path).unwrap();
+
+        TEMPLATE.replace("/*DATA*/", &data)
+    }
+
+    fn js_list(items: impl Iterator<Item = String>) -> String {
+        let items: Vec<_> = items.collect();
+        format!("[{}]", items.join(","))
+    }
+}
+
+impl SolverObserver for Recorder {
+    fn iteration_started<S: Storage>(&mut self, _maze: &Maze<S>) {
+        self.step();
+    }
+
+    fn cell_updated(&mut self, idx: usize, _old: Option<usize>, new: usize) {
+        self.changed.push((idx, new));
+        self.frontier.updated(idx);
+    }
+
+    fn node_popped<S: Storage>(&mut self, _maze: &Maze<S>, idx: usize, _priority: usize) {
+        self.step();
+        self.frontier.popped(idx);
+    }
+
+    fn node_pushed(&mut self, idx: usize, _priority: usize) {
+        self.frontier.pushed(idx);
+    }
+
+    fn finished<S: Storage>(&mut self, _maze: &Maze<S>) {
+        self.step();
+    }
+}
+
+const TEMPLATE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Maze solving report</title>
+<style>
+body { font-family: sans-serif; background: #f4f4f4; margin: 1em; }
+#controls { margin: 0.5em 0; display: flex; gap: 1em; align-items: center; }
+#timeline { width: 40em; }
+canvas { background: white; border: 1px solid #999; image-rendering: pixelated; }
+</style>
+</head>
+<body>
+<h3>Maze solving report</h3>
+<div id="controls">
+<button id="play">Play</button>
+<input id="timeline" type="range" min="0" value="0">
+<span id="label"></span>
+<label><input id="showPath" type="checkbox" checked> path on last step</label>
+</div>
+<canvas id="maze"></canvas>
+<script>
+/*DATA*/
+const canvas = document.getElementById("maze");
+const ctx = canvas.getContext("2d");
+const timeline = document.getElementById("timeline");
+const label = document.getElementById("label");
+const showPath = document.getElementById("showPath");
+const play = document.getElementById("play");
+
+const size = Math.max(2, Math.min(24, Math.floor(900 / W)));
+canvas.width = W * size;
+canvas.height = H * size;
+timeline.max = STEPS.length;
+
+let costs, current;
+let maxCost = 1;
+for (let i = 1; i < INITIAL.length; i += 2) maxCost = Math.max(maxCost, INITIAL[i]);
+for (const [changed] of STEPS)
+  for (let i = 1; i < changed.length; i += 2) maxCost = Math.max(maxCost, changed[i]);
+
+function reset() {
+  costs = new Array(W * H).fill(null);
+  for (let i = 0; i < INITIAL.length; i += 2) costs[INITIAL[i]] = INITIAL[i + 1];
+  current = 0;
+}
+
+function seek(step) {
+  if (step < current) reset();
+  for (; current < step; current++) {
+    const changed = STEPS[current][0];
+    for (let i = 0; i < changed.length; i += 2) costs[changed[i]] = changed[i + 1];
+  }
+}
+
+function draw() {
+  const step = Number(timeline.value);
+  seek(step);
+  const frontier = new Set(step > 0 ? STEPS[step - 1][1] : []);
+
+  ctx.font = Math.floor(size * 0.6) + "px monospace";
+  ctx.textAlign = "center";
+  ctx.textBaseline = "middle";
+  for (let idx = 0; idx < W * H; idx++) {
+    const x = (idx % W) * size, y = Math.floor(idx / W) * size;
+    const cost = costs[idx];
+    if (WALLS[idx] !== "0") ctx.fillStyle = "#333";
+    else if (cost === null) ctx.fillStyle = "#fff";
+    else if (frontier.has(idx)) ctx.fillStyle = "#f5c400";
+    else ctx.fillStyle = "hsl(" + (220 - 180 * cost / maxCost) + ", 70%, 70%)";
+    ctx.fillRect(x, y, size, size);
+    if (cost !== null && size >= 14) {
+      ctx.fillStyle = "#000";
+      ctx.fillText(cost, x + size / 2, y + size / 2);
+    }
+  }
+
+  if (showPath.checked && step === STEPS.length && PATH.length > 2) {
+    ctx.strokeStyle = "#d00";
+    ctx.lineWidth = Math.max(1, size / 4);
+    ctx.beginPath();
+    for (let i = 0; i < PATH.length; i += 2)
+      ctx.lineTo((PATH[i] + 0.5) * size, (PATH[i + 1] + 0.5) * size);
+    ctx.stroke();
+  }
+
+  label.textContent = "step " + step + " / " + STEPS.length;
+}
+
+let timer = null;
+play.onclick = () => {
+  if (timer !== null) {
+    clearInterval(timer);
+    timer = null;
+    play.textContent = "Play";
+    return;
+  }
+  if (Number(timeline.value) === STEPS.length) timeline.value = 0;
+  play.textContent = "Pause";
+  timer = setInterval(() => {
+    if (Number(timeline.value) >= STEPS.length) return play.onclick();
+    timeline.value = Number(timeline.value) + 1;
+    draw();
+  }, Math.max(10, 3000 / Math.max(1, STEPS.length)));
+};
+timeline.oninput = draw;
+showPath.onchange = draw;
+
+reset();
+timeline.value = STEPS.length;
+draw();
+</script>
+</body>
+</html>
+"##;
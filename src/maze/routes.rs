@@ -0,0 +1,285 @@
+//! K shortest routes
+//!
+//! Yen's algorithm - the best route is found with flood, and every next one is the cheapest
+//! deviation from any already found route. For every field of the last found route (spur), the
+//! route up to it (root) is kept, and the rest is found again, without going through the root
+//! again, and without continuing from the spur exactly as any found route with the same root does.
+//!
+//! Cost of a move depends on the direction the field was approached from, so routes are really
+//! paths over states (field, direction it is approached from). Simple routes never go through the
+//! same field twice, so the deviation is found with flood on a maze with the whole root walled -
+//! flood works on fields, not on moves between them, so moves from the spur can't be removed
+//! directly, and instead every field it can be left to (except blocked ones) is seeded with the
+//! cost of going there from the root. Other routes may go through the same field again, just
+//! never in the same state - it can't be expressed with walls, so their deviations are found with
+//! 0-1 BFS directly over states, with root states and moves of found routes from the spur removed.
+
+use super::{flood, Dir, Field, Limits, Maze, Status, Storage};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::time::Instant;
+
+/// Field index with direction it is approached from (`Dir::ANY` for the start)
+type State = (usize, Dir);
+
+/// Settings of `k_shortest`
+#[derive(Clone, Copy, Debug)]
+pub struct RouteOptions {
+    /// Number of routes to find
+    pub k: usize,
+    /// Every route has to go through every field at most once; Otherwise route may go through the
+    /// same field more times, but never approached from the same direction, and never going back
+    /// to the field it just came from
+    pub simple: bool,
+    /// Minimal number of fields every returned route has to have, which are not on any other
+    /// returned route - checked for both routes of every pair (0 accepts any distinct route)
+    pub diversity: usize,
+}
+
+/// Single route from start to exit
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Route {
+    /// Number of turns on the route
+    pub cost: usize,
+    /// Coordinates of all fields from start to exit
+    pub path: Vec<(usize, usize)>,
+}
+
+impl PartialOrd for Route {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Route {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Reversed, as it is designed to be used in max-heap to look for the cheapest route
+        let left = (std::cmp::Reverse(self.cost), std::cmp::Reverse(&self.path));
+        let right = (
+            std::cmp::Reverse(other.cost),
+            std::cmp::Reverse(&other.path),
+        );
+        left.cmp(&right)
+    }
+}
+
+/// Copy of the maze with nothing calculated
+fn cleared<S: Storage>(maze: &Maze<S>) -> Maze<S> {
+    let fields = maze.fields().map(|field| match field {
+        Field::Calculated(_, _) => Field::Empty,
+        field => field,
+    });
+
+    Maze {
+        maze: S::from_fields(fields),
+        w: maze.w,
+    }
+}
+
+/// States which can be reached with single move from given one, with cost of the move. Going
+/// back to the field the state is approached from is never a move - it would only make routes
+/// bouncing back from dead ends.
+fn moves<S: Storage>(
+    maze: &Maze<S>,
+    (idx, dir): State,
+) -> impl Iterator<Item = (State, usize)> + '_ {
+    let back = match dir {
+        Dir::ANY => None,
+        dir => Some(maze.in_dir_idx(idx, dir)),
+    };
+
+    Dir::ANY
+        .iter()
+        .filter_map(move |ndir| Some((maze.forward(idx, ndir)?, ndir)))
+        .filter(move |(next, _)| Some(*next) != back && maze.get(*next) != Field::Wall)
+        .map(move |(next, ndir)| ((next, ndir), !dir.has_all(ndir) as usize))
+}
+
+/// The cheapest continuation from given state to the exit (as indices of following fields), never
+/// going through removed states, nor from given state to blocked fields - 0-1 BFS over states.
+/// Solvers keep only the best cost of every field, so they can't find routes going through the
+/// same field again in another state, and this needs its own search. `None` if there is no such
+/// continuation, or if limits are exceeded (every expanded state is a step).
+fn deviation<S: Storage>(
+    maze: &Maze<S>,
+    from: State,
+    exit: usize,
+    blocked: &HashSet<usize>,
+    removed: &HashSet<State>,
+    limits: &Limits,
+) -> Option<Vec<usize>> {
+    let start = Instant::now();
+    // The best cost of every visited state, with the state it is reached from
+    let mut visited: HashMap<State, (usize, State)> = HashMap::new();
+    let mut queue = VecDeque::from([(from, 0)]);
+
+    let mut steps = 0;
+    while let Some((state, cost)) = queue.pop_front() {
+        if limits.exceeded(start, steps) {
+            return None;
+        }
+        steps += 1;
+
+        if state != from && visited[&state].0 < cost {
+            continue;
+        }
+
+        if state.0 == exit {
+            let mut tail = vec![];
+            let mut state = state;
+            while state != from {
+                tail.push(state.0);
+                state = visited[&state].1;
+            }
+            tail.reverse();
+            return Some(tail);
+        }
+
+        for (next, mcost) in moves(maze, state) {
+            if removed.contains(&next) || (state == from && blocked.contains(&next.0)) {
+                continue;
+            }
+
+            let ncost = cost + mcost;
+            if visited.get(&next).is_none_or(|(best, _)| ncost < *best) {
+                visited.insert(next, (ncost, state));
+                if mcost == 0 {
+                    queue.push_front((next, ncost));
+                } else {
+                    queue.push_back((next, ncost));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Direction every field of route is approached from, with cost of reaching it; The first field
+/// is the start, so it is approached from any direction with no cost
+fn walk<S: Storage>(maze: &Maze<S>, path: &[usize]) -> Vec<(Dir, usize)> {
+    let mut walk = vec![(Dir::ANY, 0)];
+    for step in path.windows(2) {
+        let (pdir, cost) = walk[walk.len() - 1];
+        let dir = maze.approach(step[0], step[1]).unwrap();
+        walk.push((dir, cost + !pdir.has_all(dir) as usize));
+    }
+    walk
+}
+
+/// Finds up to `k` cheapest distinct routes from `start` to `exit` (cheapest first). The best
+/// route, and the best simple route from every deviation, are found with flood, as it handles
+/// mazes seeded with costs (A* may never finish on them). Maze may be already solved - only its
+/// walls are used. Every deviation search
+/// is stopped by given limits, and deviation it was looking for is skipped if it is not finished.
+pub fn k_shortest<S: Storage>(
+    maze: &Maze<S>,
+    start: (usize, usize),
+    exit: (usize, usize),
+    options: RouteOptions,
+    limits: &Limits,
+) -> Vec<Route> {
+    let maze = cleared(maze);
+    let route = |path: Vec<usize>| Route {
+        cost: walk(&maze, &path).last().unwrap().1,
+        path: path.into_iter().map(|idx| maze.coords(idx)).collect(),
+    };
+
+    let mut initial = maze.clone();
+    initial.set_start(start.0, start.1);
+    let mut candidates = BinaryHeap::new();
+    if let (solved, Status::Found(_)) = flood(initial, exit.0, exit.1, &mut (), limits) {
+        if let Some(path) = solved.path(start, exit) {
+            candidates.push(route(path.iter().map(|(x, y)| maze.idx(*x, *y)).collect()));
+        }
+    }
+
+    // All routes which were ever candidates, so the same deviation found from another spur is
+    // not repeated
+    let mut seen: HashSet<_> = candidates.iter().map(|route| route.path.clone()).collect();
+    let mut found: Vec<Vec<usize>> = vec![];
+    let mut routes: Vec<Route> = vec![];
+    while routes.len() < options.k {
+        let next = match candidates.pop() {
+            Some(next) => next,
+            None => break,
+        };
+
+        // Every pair of returned routes has to differ in both directions - the new route has to
+        // have enough fields not on the other one, and the other one enough fields not on it
+        let fields: HashSet<_> = next.path.iter().collect();
+        let diverse = routes.iter().all(|other| {
+            let other_fields: HashSet<_> = other.path.iter().collect();
+            fields.difference(&other_fields).count() >= options.diversity
+                && other_fields.difference(&fields).count() >= options.diversity
+        });
+
+        // Route is used for finding deviations even if it is not diverse enough, as routes
+        // deviating from it may be
+        let path: Vec<_> = next.path.iter().map(|(x, y)| maze.idx(*x, *y)).collect();
+        let steps = walk(&maze, &path);
+        found.push(path);
+        if diverse {
+            routes.push(next);
+        }
+
+        let path = &found[found.len() - 1];
+        for spur in 0..path.len() - 1 {
+            let root = &path[..=spur];
+            let (dir, cost) = steps[spur];
+
+            let blocked: HashSet<_> = found
+                .iter()
+                .filter(|other| other.len() > spur + 1 && other[..=spur] == *root)
+                .map(|other| other[spur + 1])
+                .collect();
+
+            let tail = if options.simple {
+                let mut deviation = maze.clone();
+                for idx in root {
+                    deviation.set(*idx, Field::Wall);
+                }
+
+                let seeds: Vec<_> = moves(&maze, (path[spur], dir))
+                    .filter(|((idx, _), _)| {
+                        !blocked.contains(idx) && deviation.get(*idx) == Field::Empty
+                    })
+                    .collect();
+                for ((idx, sdir), mcost) in seeds.iter().copied() {
+                    deviation.set(idx, Field::Calculated(sdir, cost + mcost));
+                }
+
+                match flood(deviation, exit.0, exit.1, &mut (), limits) {
+                    // Any of seeds may be the beginning of the best deviation - if route goes
+                    // through another seed, reconstruction stops there
+                    (solved, Status::Found(_)) => seeds.iter().find_map(|((idx, _), _)| {
+                        let tail = solved.path(maze.coords(*idx), exit)?;
+                        Some(tail.iter().map(|(x, y)| maze.idx(*x, *y)).collect())
+                    }),
+                    _ => None,
+                }
+            } else {
+                let removed: HashSet<_> = root
+                    .iter()
+                    .zip(&steps)
+                    .map(|(idx, (dir, _))| (*idx, *dir))
+                    .collect();
+                let exit = maze.idx(exit.0, exit.1);
+                deviation(&maze, (path[spur], dir), exit, &blocked, &removed, limits)
+            };
+
+            if let Some(tail) = tail {
+                let path: Vec<_> = root.iter().copied().chain(tail).collect();
+                let candidate = route(path);
+                if seen.insert(candidate.path.clone()) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+    }
+
+    debug_assert!(
+        routes.windows(2).all(|pair| pair[0].cost <= pair[1].cost),
+        "Routes not ordered by cost"
+    );
+    routes
+}
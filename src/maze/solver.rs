@@ -0,0 +1,120 @@
+//! Step-wise solving
+//!
+//! Instead of running algorithm to the end in single call, solver can be advanced step by step,
+//! so calculation can be spread in time (eg. limited time in every frame of a game loop).
+
+use super::{Dense, Maze, Storage};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// State of solver after a step
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Status {
+    /// Calculation is not finished yet
+    Running,
+    /// Best path to exit is found with given cost
+    Found(usize),
+    /// There is no path to exit
+    Exhausted,
+    /// Calculation was stopped by limits before it finished
+    Incomplete(Bound),
+}
+
+/// Bounds of the exit cost known at some point of calculation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Bound {
+    /// Best path cannot be cheaper than this
+    pub lower: usize,
+    /// Cost of the best path found so far, if any
+    pub upper: Option<usize>,
+}
+
+/// Token for stopping calculation from outside (eg. from another thread)
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Limits after which calculation is stopped even if it is not finished
+#[derive(Clone, Default, Debug)]
+pub struct Limits {
+    pub cancel: Option<CancelToken>,
+    /// Wall-clock time of whole calculation
+    pub timeout: Option<Duration>,
+    /// Number of steps (iterations for flood, node expansions for A*)
+    pub max_steps: Option<usize>,
+}
+
+impl Limits {
+    /// Checks if calculation started at `start`, which already performed `steps` steps should be
+    /// stopped
+    pub(crate) fn exceeded(&self, start: Instant, steps: usize) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+            || self
+                .timeout
+                .is_some_and(|timeout| start.elapsed() >= timeout)
+            || self.max_steps.is_some_and(|max_steps| steps >= max_steps)
+    }
+}
+
+/// Resumable path finding algorithm, working on maze kept in given storage
+pub trait Solver<S: Storage = Dense> {
+    /// Advances calculation by single step (iteration for flood, node expansion for A*). Once
+    /// calculation is finished, the final status is returned without any more work.
+    fn step(&mut self) -> Status;
+
+    /// Current state of the maze
+    fn maze(&self) -> &Maze<S>;
+
+    /// Bounds of the exit cost basing on current state
+    fn bound(&self) -> Bound;
+
+    /// Performs steps until calculation is finished
+    fn run(&mut self) -> Status {
+        loop {
+            match self.step() {
+                Status::Running => (),
+                status => return status,
+            }
+        }
+    }
+
+    /// Performs steps until calculation is finished, or given time elapses. Single step is never
+    /// interrupted, so budget may be slightly exceeded.
+    fn run_for(&mut self, budget: Duration) -> Status {
+        let start = Instant::now();
+        loop {
+            match self.step() {
+                Status::Running if start.elapsed() < budget => (),
+                status => return status,
+            }
+        }
+    }
+
+    /// Performs steps until calculation is finished, or any of limits is exceeded - in such case
+    /// `Status::Incomplete` is returned. Limits are checked between steps, so single step is never
+    /// interrupted.
+    fn run_limited(&mut self, limits: &Limits) -> Status {
+        let start = Instant::now();
+        let mut steps = 0;
+        loop {
+            if limits.exceeded(start, steps) {
+                return Status::Incomplete(self.bound());
+            }
+
+            match self.step() {
+                Status::Running => steps += 1,
+                status => return status,
+            }
+        }
+    }
+}
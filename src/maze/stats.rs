@@ -0,0 +1,168 @@
+//! Statistics of single calculation, collected as solver observer
+
+use super::{Maze, SolverObserver, Storage};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// Format of reported data
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    /// Human readable text
+    Text,
+    Json,
+}
+
+#[derive(Debug)]
+pub struct Stats {
+    started: Instant,
+    /// Time from creation to finishing calculation (`None` if it didn't finish)
+    wall_time: Option<Duration>,
+    /// Number of fields changed in every flood iteration
+    iterations: Vec<usize>,
+    pops: usize,
+    pushes: usize,
+    /// Current A* queue size
+    queue: usize,
+    peak_queue: usize,
+    /// All field updates, including the same field updated multiple times
+    updates: usize,
+    /// Fields updated at least once
+    touched: Vec<bool>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stats {
+    /// Creates stats collector, time of calculation is measured from this point
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            wall_time: None,
+            iterations: vec![],
+            pops: 0,
+            pushes: 0,
+            queue: 0,
+            peak_queue: 0,
+            updates: 0,
+            touched: vec![],
+        }
+    }
+
+    pub fn wall_time(&self) -> Duration {
+        self.wall_time.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations.len()
+    }
+
+    pub fn pops(&self) -> usize {
+        self.pops
+    }
+
+    pub fn pushes(&self) -> usize {
+        self.pushes
+    }
+
+    pub fn peak_queue(&self) -> usize {
+        self.peak_queue
+    }
+
+    pub fn updates(&self) -> usize {
+        self.updates
+    }
+
+    pub fn touched(&self) -> usize {
+        self.touched.iter().filter(|touched| **touched).count()
+    }
+
+    /// Formats stats as multiline text, or single JSON object
+    pub fn report(&self, format: Format) -> String {
+        match format {
+            Format::Text => self.text(),
+            Format::Json => self.json(),
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut text = String::new();
+        writeln!(text, "wall time: {:?}", self.wall_time()).unwrap();
+        writeln!(text, "cells updated: {}", self.updates).unwrap();
+        writeln!(text, "cells touched: {}", self.touched()).unwrap();
+
+        if !self.iterations.is_empty() {
+            let max = self.iterations.iter().max().unwrap();
+            let avg = self.iterations.iter().sum::<usize>() as f64 / self.iterations.len() as f64;
+            writeln!(text, "flood iterations: {}", self.iterations.len()).unwrap();
+            writeln!(
+                text,
+                "cells changed per iteration: avg {:.1}, max {}",
+                avg, max
+            )
+            .unwrap();
+        }
+
+        if self.pushes > 0 {
+            writeln!(text, "queue pops: {}", self.pops).unwrap();
+            writeln!(text, "queue pushes: {}", self.pushes).unwrap();
+            writeln!(text, "peak queue size: {}", self.peak_queue).unwrap();
+        }
+
+        text
+    }
+
+    fn json(&self) -> String {
+        let iterations: Vec<_> = self.iterations.iter().map(usize::to_string).collect();
+        format!(
+            concat!(
+                "{{\"wall_time_us\":{},\"updates\":{},\"touched\":{},\"iterations\":{},",
+                "\"changed_per_iteration\":[{}],\"pops\":{},\"pushes\":{},\"peak_queue\":{}}}"
+            ),
+            self.wall_time().as_micros(),
+            self.updates,
+            self.touched(),
+            self.iterations.len(),
+            iterations.join(","),
+            self.pops,
+            self.pushes,
+            self.peak_queue,
+        )
+    }
+}
+
+impl SolverObserver for Stats {
+    fn iteration_started<S: Storage>(&mut self, _maze: &Maze<S>) {
+        self.iterations.push(0);
+    }
+
+    fn cell_updated(&mut self, idx: usize, _old: Option<usize>, _new: usize) {
+        self.updates += 1;
+        if let Some(changed) = self.iterations.last_mut() {
+            *changed += 1;
+        }
+
+        if idx >= self.touched.len() {
+            self.touched.resize(idx + 1, false);
+        }
+        self.touched[idx] = true;
+    }
+
+    fn node_popped<S: Storage>(&mut self, _maze: &Maze<S>, _idx: usize, _priority: usize) {
+        self.pops += 1;
+        self.queue -= 1;
+    }
+
+    fn node_pushed(&mut self, _idx: usize, _priority: usize) {
+        self.pushes += 1;
+        self.queue += 1;
+        self.peak_queue = self.peak_queue.max(self.queue);
+    }
+
+    fn finished<S: Storage>(&mut self, _maze: &Maze<S>) {
+        self.wall_time = Some(self.started.elapsed());
+    }
+}
@@ -0,0 +1,338 @@
+//! Storage of maze fields
+//!
+//! By default all fields are kept packed in single buffer (`Dense`), which is the fastest, but
+//! takes memory proportional to the maze area no matter what is in it. For huge mazes which are
+//! mostly walls (thin corridors) or mostly empty (open floor plans) `Sparse` keeps runs of the
+//! same field, so memory scales with features instead of area.
+//!
+//! Runs are kept separately for blocks of fields, and block in which runs would take more memory
+//! than fields themselves (eg. already calculated part of the maze, where costs differ on every
+//! field) keeps all its fields packed, exactly as `Dense` does. This way `Sparse` never takes more
+//! memory than `Dense`, except few bytes for every block.
+
+use super::{Cell, Field};
+use rayon::prelude::*;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+
+/// Number of fields in single block of `Sparse`, so index of field in block always fits in `u16`
+const BLOCK: usize = 4096;
+
+/// Storage of all maze fields, addressed by index
+pub trait Storage: Clone + Send + Sync + Sized {
+    /// Creates storage from all fields of the maze
+    fn from_fields(fields: impl Iterator<Item = Field>) -> Self;
+
+    /// Number of all fields
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gives field with given index, which have to be in range
+    fn get(&self, idx: usize) -> Field;
+
+    /// Gives field with given index, or wall if it is out of range; It is how flood reads
+    /// neighbours, so storage may do it with single bounds check
+    fn get_or_wall(&self, idx: usize) -> Field {
+        if idx < self.len() {
+            self.get(idx)
+        } else {
+            Field::Wall
+        }
+    }
+
+    /// Stores field on given index, which have to be in range
+    fn set(&mut self, idx: usize, field: Field);
+
+    /// Indices of all calculated fields
+    fn calculated(&self) -> Vec<usize>;
+
+    /// Recalculates every field in parallel with given function, which gives the new field and
+    /// the cost it is updated to (if any); Returns all updates as (index, cost).
+    fn par_fill(
+        &mut self,
+        f: impl Fn(usize) -> (Field, Option<usize>) + Send + Sync,
+    ) -> Vec<(usize, usize)>;
+}
+
+/// All fields packed in single buffer
+#[derive(Clone)]
+pub struct Dense(Box<[Cell]>);
+
+impl Deref for Dense {
+    type Target = [Cell];
+
+    fn deref(&self) -> &[Cell] {
+        &self.0
+    }
+}
+
+impl DerefMut for Dense {
+    fn deref_mut(&mut self) -> &mut [Cell] {
+        &mut self.0
+    }
+}
+
+impl From<Box<[Cell]>> for Dense {
+    fn from(cells: Box<[Cell]>) -> Self {
+        Dense(cells)
+    }
+}
+
+// Solvers are generic over storage, so they are instantiated in the crate using them - methods
+// used by flood kernel are inlined explicitly, as otherwise they would be called across crates
+impl Storage for Dense {
+    fn from_fields(fields: impl Iterator<Item = Field>) -> Self {
+        Dense(fields.map(Cell::from).collect())
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> Field {
+        self.0[idx].into()
+    }
+
+    #[inline]
+    fn set(&mut self, idx: usize, field: Field) {
+        self.0[idx] = field.into();
+    }
+
+    #[inline]
+    fn get_or_wall(&self, idx: usize) -> Field {
+        self.0.get(idx).map_or(Field::Wall, |cell| (*cell).into())
+    }
+
+    fn calculated(&self) -> Vec<usize> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| matches!(Field::from(**cell), Field::Calculated(_, _)))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    fn par_fill(
+        &mut self,
+        f: impl Fn(usize) -> (Field, Option<usize>) + Send + Sync,
+    ) -> Vec<(usize, usize)> {
+        self.0
+            .par_iter_mut()
+            .enumerate()
+            .filter_map(|(idx, cell)| {
+                let (field, update) = f(idx);
+                *cell = field.into();
+                update.map(|cost| (idx, cost))
+            })
+            .collect()
+    }
+}
+
+/// Single run of the same field - index (in block) of the first field of it, and the field
+type Run = (u16, Cell);
+
+/// Fields of single block of `Sparse`
+#[derive(Clone)]
+enum Block {
+    /// Runs of the same field, ordered by their first field
+    Runs(Vec<Run>),
+    /// All fields packed
+    Dense(Box<[Cell]>),
+}
+
+impl Block {
+    /// If given number of runs takes at most half of memory of packed fields of block with given
+    /// length - vector of runs may have spare capacity up to the number of runs, so even then it
+    /// never takes more than packed fields
+    fn fits(runs: usize, len: usize) -> bool {
+        2 * runs * size_of::<Run>() <= len * size_of::<Cell>()
+    }
+
+    /// Creates block from all its fields, as runs if they fit
+    fn new(cells: &[Cell]) -> Self {
+        let mut runs: Vec<Run> = vec![];
+        for (idx, cell) in cells.iter().enumerate() {
+            if runs.last().is_some_and(|(_, last)| last == cell) {
+                continue;
+            }
+            if !Self::fits(runs.len() + 1, cells.len()) {
+                return Block::Dense(cells.into());
+            }
+            runs.push((idx as u16, *cell));
+        }
+
+        runs.shrink_to_fit();
+        Block::Runs(runs)
+    }
+
+    /// Gives field with given index in block
+    fn get(&self, idx: usize) -> Cell {
+        match self {
+            Block::Runs(runs) => {
+                let run = runs.partition_point(|(start, _)| *start as usize <= idx) - 1;
+                runs[run].1
+            }
+            Block::Dense(cells) => cells[idx],
+        }
+    }
+
+    /// Stores field on given index in block with given length; Once runs don't fit anymore, block
+    /// is packed
+    fn set(&mut self, idx: usize, cell: Cell, len: usize) {
+        let runs = match self {
+            Block::Runs(runs) => runs,
+            Block::Dense(cells) => {
+                cells[idx] = cell;
+                return;
+            }
+        };
+
+        let run = runs.partition_point(|(start, _)| *start as usize <= idx) - 1;
+        let (start, old) = runs[run];
+        if old == cell {
+            return;
+        }
+
+        // Rest of the run after changed field keeps old field
+        let end = runs.get(run + 1).map_or(len, |(start, _)| *start as usize);
+        if idx + 1 < end {
+            runs.insert(run + 1, (idx as u16 + 1, old));
+        }
+
+        let run = if start as usize == idx {
+            runs[run].1 = cell;
+            run
+        } else {
+            runs.insert(run + 1, (idx as u16, cell));
+            run + 1
+        };
+
+        // Changed field may join the next run and the previous one
+        if runs
+            .get(run + 1)
+            .is_some_and(|(start, next)| *start as usize == idx + 1 && *next == cell)
+        {
+            runs.remove(run + 1);
+        }
+        if run > 0 && runs[run - 1].1 == cell {
+            runs.remove(run);
+        }
+
+        if !Self::fits(runs.len(), len) {
+            let cells: Vec<_> = (0..len).map(|idx| self.get(idx)).collect();
+            *self = Block::Dense(cells.into_boxed_slice());
+        }
+    }
+}
+
+/// Fields kept as runs of the same field (see module docs)
+#[derive(Clone)]
+pub struct Sparse {
+    len: usize,
+    /// Blocks of `BLOCK` fields, the last one may be shorter
+    blocks: Vec<Block>,
+}
+
+impl Sparse {
+    /// Number of fields in given block
+    fn block_len(&self, block: usize) -> usize {
+        BLOCK.min(self.len - block * BLOCK)
+    }
+}
+
+impl Storage for Sparse {
+    fn from_fields(fields: impl Iterator<Item = Field>) -> Self {
+        // Fields are streamed into blocks, so only single block is ever kept unpacked
+        let mut len = 0;
+        let mut blocks = vec![];
+        let mut cells = Vec::with_capacity(BLOCK);
+        for cell in fields.map(Cell::from) {
+            cells.push(cell);
+            len += 1;
+            if cells.len() == BLOCK {
+                blocks.push(Block::new(&cells));
+                cells.clear();
+            }
+        }
+
+        if !cells.is_empty() {
+            blocks.push(Block::new(&cells));
+        }
+        blocks.shrink_to_fit();
+
+        Sparse { len, blocks }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, idx: usize) -> Field {
+        self.blocks[idx / BLOCK].get(idx % BLOCK).into()
+    }
+
+    fn set(&mut self, idx: usize, field: Field) {
+        let len = self.block_len(idx / BLOCK);
+        self.blocks[idx / BLOCK].set(idx % BLOCK, field.into(), len);
+    }
+
+    fn calculated(&self) -> Vec<usize> {
+        let calculated = |cell: Cell| matches!(Field::from(cell), Field::Calculated(_, _));
+        let mut result = vec![];
+        for (block, fields) in self.blocks.iter().enumerate() {
+            let base = block * BLOCK;
+            match fields {
+                Block::Runs(runs) => {
+                    for (run, (start, cell)) in runs.iter().enumerate() {
+                        if calculated(*cell) {
+                            let end = runs
+                                .get(run + 1)
+                                .map_or(self.block_len(block), |(start, _)| *start as usize);
+                            result.extend(base + *start as usize..base + end);
+                        }
+                    }
+                }
+                Block::Dense(cells) => result.extend(
+                    cells
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, cell)| calculated(**cell))
+                        .map(|(idx, _)| base + idx),
+                ),
+            }
+        }
+        result
+    }
+
+    fn par_fill(
+        &mut self,
+        f: impl Fn(usize) -> (Field, Option<usize>) + Send + Sync,
+    ) -> Vec<(usize, usize)> {
+        let len = self.len;
+        self.blocks
+            .par_iter_mut()
+            .enumerate()
+            .flat_map(|(block, fields)| {
+                let base = block * BLOCK;
+                let mut updates = vec![];
+                let cells: Vec<_> = (base..len.min(base + BLOCK))
+                    .map(|idx| {
+                        let (field, update) = f(idx);
+                        if let Some(cost) = update {
+                            updates.push((idx, cost));
+                        }
+                        Cell::from(field)
+                    })
+                    .collect();
+
+                *fields = Block::new(&cells);
+                updates
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,163 @@
+//! Validation and scoring of candidate paths
+//!
+//! Candidate is valid if it goes from the start to the exit only through neighbouring fields
+//! which are not walls - neighbouring the same way as for solvers, so the last field of a row and
+//! the first field of the next one are neighbours too. Its cost is counted the same way solvers count it - the first move is
+//! free, and every next move costs one if it is not in the same direction as the previous one. To
+//! tell if it is optimal, the maze is solved with flood, and candidate is compared to its bounds -
+//! so even if solving is stopped by limits, the answer may still be known.
+
+use super::{execute, flood, Bound, Dir, Field, Instruction, Limits, Maze, Status, Storage};
+use std::io::BufRead;
+
+/// Reason why candidate path is not valid
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Violation {
+    /// Token of candidate is neither coordinates nor instruction
+    Syntax(String),
+    /// Path doesn't begin on the start
+    Start,
+    /// Path doesn't finish on the exit
+    Exit,
+    /// Field on given step of path is outside of the maze
+    Outside(usize),
+    /// Field with given coordinates is a wall
+    Wall((usize, usize)),
+    /// Fields with given coordinates follow each other on path, but are not neighbours
+    Jump((usize, usize), (usize, usize)),
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Violation::Syntax(token) => write!(f, "can't parse `{}`", token),
+            Violation::Start => f.write_str("path doesn't begin on the start"),
+            Violation::Exit => f.write_str("path doesn't finish on the exit"),
+            Violation::Outside(step) => write!(f, "step {} leaves the maze", step),
+            Violation::Wall((x, y)) => write!(f, "wall at {},{}", x, y),
+            Violation::Jump((fx, fy), (tx, ty)) => {
+                write!(f, "{},{} and {},{} are not neighbours", fx, fy, tx, ty)
+            }
+        }
+    }
+}
+
+/// Parses candidate path - either coordinates of its fields (`x,y` separated with whitespaces), or
+/// program of robot entering the maze on the start (as `instructions` gives it)
+pub fn parse_candidate<S: Storage>(
+    maze: &Maze<S>,
+    candidate: &str,
+    start: (usize, usize),
+) -> Result<Vec<(usize, usize)>, Violation> {
+    let syntax = |token: &str| Violation::Syntax(token.to_owned());
+    let tokens: Vec<_> = candidate.split_whitespace().collect();
+
+    if tokens.first().is_some_and(|token| token.contains(',')) {
+        tokens
+            .iter()
+            .map(|token| {
+                let (x, y) = token.split_once(',').ok_or_else(|| syntax(token))?;
+                Ok((
+                    x.parse().map_err(|_| syntax(token))?,
+                    y.parse().map_err(|_| syntax(token))?,
+                ))
+            })
+            .collect()
+    } else {
+        let program = tokens
+            .iter()
+            .map(|token| Instruction::parse(token).ok_or_else(|| syntax(token)))
+            .collect::<Result<Vec<_>, _>>()?;
+        execute(maze, &program, start, Dir::LEFT).map_err(Violation::Outside)
+    }
+}
+
+/// Checks if path goes from `start` to `exit` through neighbouring not wall fields, and gives
+/// its cost if so. Maze may be already solved - only its walls are used.
+pub fn score<S: Storage>(
+    maze: &Maze<S>,
+    path: &[(usize, usize)],
+    start: (usize, usize),
+    exit: (usize, usize),
+) -> Result<usize, Violation> {
+    if path.first() != Some(&start) {
+        return Err(Violation::Start);
+    }
+
+    let mut dir = Dir::ANY;
+    let mut cost = 0;
+    for (step, (x, y)) in path.iter().copied().enumerate() {
+        if x >= maze.w || y >= maze.height() {
+            return Err(Violation::Outside(step));
+        }
+
+        if maze.field(x, y) == Field::Wall {
+            return Err(Violation::Wall((x, y)));
+        }
+
+        if let Some(prev) = step.checked_sub(1).map(|prev| path[prev]) {
+            let next = maze
+                .approach(maze.idx(prev.0, prev.1), maze.idx(x, y))
+                .ok_or(Violation::Jump(prev, (x, y)))?;
+            cost += !dir.has_all(next) as usize;
+            dir = next;
+        }
+    }
+
+    if path.last() != Some(&exit) {
+        return Err(Violation::Exit);
+    }
+
+    Ok(cost)
+}
+
+/// Verifies candidate path (see `parse_candidate`) through maze from input. Prints cost of the
+/// candidate and the optimal cost (or its bounds, if solving is stopped by given limits), and
+/// whether candidate is `OPTIMAL`, `SUBOPTIMAL` or it is `UNKNOWN` - or just `INVALID` with the
+/// reason. Returns `false` if candidate is known not to be optimal.
+pub fn verify(x: usize, y: usize, input: impl BufRead, candidate: &str, limits: &Limits) -> bool {
+    let mut maze: Maze = Maze::from_input(x, y, input);
+    maze.set_start(0, 1);
+    let (start, exit) = ((0, 1), (x - 1, y - 2));
+
+    let scored =
+        parse_candidate(&maze, candidate, start).and_then(|path| score(&maze, &path, start, exit));
+    let cost = match scored {
+        Ok(cost) => cost,
+        Err(violation) => {
+            println!("INVALID {}", violation);
+            return false;
+        }
+    };
+    println!("COST {}", cost);
+
+    let Bound { lower, upper } = match flood(maze, exit.0, exit.1, &mut (), limits) {
+        (_, Status::Found(optimum)) => {
+            println!("OPTIMUM {}", optimum);
+            Bound {
+                lower: optimum,
+                upper: Some(optimum),
+            }
+        }
+        (_, Status::Incomplete(bound)) => {
+            match bound.upper {
+                Some(upper) => println!("OPTIMUM INCOMPLETE >={} <={}", bound.lower, upper),
+                None => println!("OPTIMUM INCOMPLETE >={}", bound.lower),
+            }
+            bound
+        }
+        // Valid candidate reaches the exit, so solver can't give up on it
+        (_, status) => unreachable!("Solver finished with {:?}", status),
+    };
+
+    if cost <= lower {
+        println!("OPTIMAL");
+        true
+    } else if upper.is_some_and(|upper| cost > upper) {
+        println!("SUBOPTIMAL");
+        false
+    } else {
+        println!("UNKNOWN");
+        true
+    }
+}
@@ -0,0 +1,119 @@
+//! Terminal visualization of solvers progress
+//!
+//! Every frame is drawn over the previous one (cursor is moved to the top left corner instead of
+//! printing the next maze below), so it is possible to watch algorithm working as an animation.
+//! Fields are colored with ANSI escape codes, so terminal supporting them is needed.
+
+use super::observer::Frontier;
+use super::{Field, Maze, SolverObserver, Storage};
+use std::collections::HashSet;
+use std::io::{stdout, Write};
+use std::time::Duration;
+
+const RESET: &str = "\x1b[0m";
+const WALL: &str = "\x1b[100m";
+const VISITED: &str = "\x1b[34m";
+const FRONTIER: &str = "\x1b[1;33m";
+const PATH: &str = "\x1b[1;30;42m";
+
+pub struct Visualizer {
+    /// Delay after every drawn frame
+    delay: Duration,
+    frontier: Frontier,
+}
+
+impl Visualizer {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            frontier: Frontier::default(),
+        }
+    }
+
+    /// Clears whole terminal, should be called before first frame
+    pub fn clear(&self) {
+        print!("\x1b[2J");
+    }
+
+    /// Draws single frame of algorithm progress
+    ///
+    /// frontier - fields on the frontier of the algorithm (just updated for flood, or waiting in
+    /// the queue for A*)
+    pub fn frame<S: Storage>(&self, maze: &Maze<S>, frontier: impl Iterator<Item = usize>) {
+        let frontier: HashSet<_> = frontier.collect();
+        self.draw(maze, |idx| {
+            if frontier.contains(&idx) {
+                FRONTIER
+            } else {
+                VISITED
+            }
+        });
+    }
+
+    /// Draws final maze state with given path marked on it
+    pub fn path<S: Storage>(&self, maze: &Maze<S>, path: &[(usize, usize)]) {
+        let path: Vec<_> = path.iter().map(|(x, y)| maze.idx(*x, *y)).collect();
+        self.draw(maze, |idx| if path.contains(&idx) { PATH } else { VISITED });
+    }
+
+    /// Draws maze with calculated fields colored by given function
+    fn draw<S: Storage>(&self, maze: &Maze<S>, color: impl Fn(usize) -> &'static str) {
+        let mut frame = String::from("\x1b[H");
+
+        for y in 0..maze.height() {
+            let line = (0..maze.w).map(|x| maze.idx(x, y));
+            for (x, field) in line.map(|idx| maze.get(idx)).enumerate() {
+                match field {
+                    Field::Empty => frame.push(' '),
+                    Field::Wall => {
+                        frame.push_str(WALL);
+                        frame.push(' ');
+                        frame.push_str(RESET);
+                    }
+                    Field::Calculated(_, distance) => {
+                        frame.push_str(color(maze.idx(x, y)));
+                        frame.push_str(&(distance % 10).to_string());
+                        frame.push_str(RESET);
+                    }
+                }
+            }
+            frame.push('\n');
+        }
+
+        let mut out = stdout();
+        out.write_all(frame.as_bytes()).unwrap();
+        out.flush().unwrap();
+        std::thread::sleep(self.delay);
+    }
+
+    /// Draws frame if anything changed since the last one
+    fn step<S: Storage>(&mut self, maze: &Maze<S>) {
+        if self.frontier.changed() {
+            let frontier = self.frontier.step();
+            self.frame(maze, frontier.into_iter());
+        }
+    }
+}
+
+impl SolverObserver for Visualizer {
+    fn iteration_started<S: Storage>(&mut self, maze: &Maze<S>) {
+        self.step(maze);
+    }
+
+    fn cell_updated(&mut self, idx: usize, _old: Option<usize>, _new: usize) {
+        self.frontier.updated(idx);
+    }
+
+    fn node_popped<S: Storage>(&mut self, maze: &Maze<S>, idx: usize, _priority: usize) {
+        self.step(maze);
+        self.frontier.popped(idx);
+    }
+
+    fn node_pushed(&mut self, idx: usize, _priority: usize) {
+        self.frontier.pushed(idx);
+    }
+
+    fn finished<S: Storage>(&mut self, maze: &Maze<S>) {
+        self.step(maze);
+    }
+}
@@ -0,0 +1,51 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the binary with given arguments and maze on its standard input
+fn run(args: &[&str], maze: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_maze"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(maze.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// Solves the maze with `--instructions`, and verifies the printed program
+fn verify_instructions(name: &str, maze: &str) {
+    let solved = run(&["maze", "--instructions"], maze);
+    assert!(solved.status.success());
+    let solved = String::from_utf8(solved.stdout).unwrap();
+    let program = solved.lines().last().unwrap();
+
+    let candidate = std::env::temp_dir().join(format!("maze-{}-{}", name, std::process::id()));
+    std::fs::write(&candidate, program).unwrap();
+    let verified = run(&["verify", candidate.to_str().unwrap()], maze);
+    std::fs::remove_file(&candidate).unwrap();
+
+    let verified = String::from_utf8(verified.stdout).unwrap();
+    assert!(
+        verified.lines().any(|line| line == "OPTIMAL"),
+        "Program `{}` is not verified as optimal:\n{}",
+        program,
+        verified
+    );
+}
+
+#[test]
+fn instructions_verify_as_optimal() {
+    verify_instructions("input", include_str!("../input"));
+}
+
+#[test]
+fn instructions_across_row_end_verify_as_optimal() {
+    // The only way to the exit is going left from the start, to the end of the first row
+    verify_instructions("wrap", "5,4\n00001\n10001\n00001\n00000\n");
+}